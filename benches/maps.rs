@@ -0,0 +1,239 @@
+//! Compares [`DirectIdMap`] against [`HashMap`] and a plain `Vec<Option<V>>`.
+//!
+//! Each operation is measured with both dense keys (`0..N`)
+//! and sparse keys (every 16th id, in a scrambled order).
+#![allow(missing_docs)]
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use idmap::DirectIdMap;
+use std::collections::HashMap;
+
+const COUNT: u32 = 10_000;
+
+fn dense_keys() -> Vec<u32> {
+    (0..COUNT).collect()
+}
+
+fn sparse_keys() -> Vec<u32> {
+    // multiplying by a number coprime to COUNT permutes the keys
+    (0..COUNT).map(|i| (i * 7919 % COUNT) * 16).collect()
+}
+
+/// The operations being benchmarked, implemented for each map type.
+trait BenchMap: Clone {
+    const NAME: &'static str;
+    fn build(keys: &[u32]) -> Self;
+    fn get(&self, key: u32) -> Option<&u32>;
+    fn remove(&mut self, key: u32) -> Option<u32>;
+    fn sum(&self) -> u32;
+}
+
+impl BenchMap for DirectIdMap<u32, u32> {
+    const NAME: &'static str = "DirectIdMap";
+    fn build(keys: &[u32]) -> Self {
+        let mut map = DirectIdMap::new();
+        for &key in keys {
+            map.insert(key, key);
+        }
+        map
+    }
+    #[inline]
+    fn get(&self, key: u32) -> Option<&u32> {
+        DirectIdMap::get(self, key)
+    }
+    #[inline]
+    fn remove(&mut self, key: u32) -> Option<u32> {
+        DirectIdMap::remove(self, key)
+    }
+    fn sum(&self) -> u32 {
+        self.values().fold(0, |acc, &value| acc.wrapping_add(value))
+    }
+}
+
+impl BenchMap for HashMap<u32, u32> {
+    const NAME: &'static str = "HashMap";
+    fn build(keys: &[u32]) -> Self {
+        let mut map = HashMap::new();
+        for &key in keys {
+            map.insert(key, key);
+        }
+        map
+    }
+    #[inline]
+    fn get(&self, key: u32) -> Option<&u32> {
+        HashMap::get(self, &key)
+    }
+    #[inline]
+    fn remove(&mut self, key: u32) -> Option<u32> {
+        HashMap::remove(self, &key)
+    }
+    fn sum(&self) -> u32 {
+        self.values().fold(0, |acc, &value| acc.wrapping_add(value))
+    }
+}
+
+impl BenchMap for Vec<Option<u32>> {
+    const NAME: &'static str = "Vec<Option>";
+    fn build(keys: &[u32]) -> Self {
+        let mut vec = Vec::new();
+        for &key in keys {
+            let index = key as usize;
+            if vec.len() <= index {
+                vec.resize(index + 1, None);
+            }
+            vec[index] = Some(key);
+        }
+        vec
+    }
+    #[inline]
+    fn get(&self, key: u32) -> Option<&u32> {
+        self.as_slice().get(key as usize)?.as_ref()
+    }
+    #[inline]
+    fn remove(&mut self, key: u32) -> Option<u32> {
+        self.get_mut(key as usize)?.take()
+    }
+    fn sum(&self) -> u32 {
+        self.iter()
+            .flatten()
+            .fold(0, |acc, &value| acc.wrapping_add(value))
+    }
+}
+
+fn bench_map<M: BenchMap>(c: &mut Criterion) {
+    for (distribution, keys) in [("dense", dense_keys()), ("sparse", sparse_keys())] {
+        let mut group = c.benchmark_group(format!("{}/{distribution}", M::NAME));
+        group.bench_function("insert", |b| b.iter(|| M::build(black_box(&keys))));
+        let map = M::build(&keys);
+        group.bench_function("get", |b| {
+            b.iter(|| {
+                for &key in &keys {
+                    black_box(map.get(black_box(key)));
+                }
+            })
+        });
+        group.bench_function("iterate", |b| b.iter(|| black_box(&map).sum()));
+        group.bench_function("remove", |b| {
+            b.iter_batched_ref(
+                || map.clone(),
+                |map| {
+                    for &key in &keys {
+                        black_box(map.remove(black_box(key)));
+                    }
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.finish();
+    }
+}
+
+/// Compares the ways of bulk-loading a [`DirectIdMap`] from unique keys.
+fn bench_bulk_load(c: &mut Criterion) {
+    for (distribution, keys) in [("dense", dense_keys()), ("sparse", sparse_keys())] {
+        let mut group = c.benchmark_group(format!("DirectIdMap/{distribution}/bulk"));
+        group.bench_function("insert", |b| {
+            b.iter(|| {
+                let mut map = DirectIdMap::new();
+                for &key in black_box(&keys) {
+                    black_box(map.insert(key, key));
+                }
+                map
+            })
+        });
+        group.bench_function("insert_unique_unchecked", |b| {
+            b.iter(|| {
+                let mut map = DirectIdMap::new();
+                for &key in black_box(&keys) {
+                    map.insert_unique_unchecked(key, key);
+                }
+                map
+            })
+        });
+        let mut sorted = keys.clone();
+        sorted.sort_unstable();
+        group.bench_function("collect_sorted", |b| {
+            b.iter(|| {
+                black_box(&sorted)
+                    .iter()
+                    .map(|&key| (key, key))
+                    .collect::<DirectIdMap<_, _>>()
+            })
+        });
+        group.bench_function("from_sorted_iter", |b| {
+            b.iter(|| {
+                DirectIdMap::from_sorted_iter(black_box(&sorted).iter().map(|&key| (key, key)))
+                    .unwrap()
+            })
+        });
+        group.finish();
+    }
+}
+
+/// Compares converting the values of a [`DirectIdMap`] in place with rebuilding it.
+fn bench_map_values(c: &mut Criterion) {
+    for (distribution, keys) in [("dense", dense_keys()), ("sparse", sparse_keys())] {
+        let mut group = c.benchmark_group(format!("DirectIdMap/{distribution}/map_values"));
+        let map = <DirectIdMap<u32, u32> as BenchMap>::build(&keys);
+        group.bench_function("map_values", |b| {
+            b.iter_batched(
+                || map.clone(),
+                |map| map.map_values(|_, value| u64::from(value) * 2),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("collect", |b| {
+            b.iter_batched(
+                || map.clone(),
+                |map| {
+                    map.into_iter()
+                        .map(|(key, value)| (key, u64::from(value) * 2))
+                        .collect::<DirectIdMap<_, _>>()
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.finish();
+    }
+}
+
+/// Compares [`DirectIdMap::get_or_insert_with`] with the entry API,
+/// counting a million keys where three quarters are repeats.
+fn bench_get_or_insert(c: &mut Criterion) {
+    let keys: Vec<u32> = (0..1_000_000u32).map(|i| i * 7919 % 250_000).collect();
+    let mut group = c.benchmark_group("DirectIdMap/get_or_insert");
+    group.bench_function("get_or_insert_with", |b| {
+        b.iter(|| {
+            let mut counts = DirectIdMap::<u32, u32>::new();
+            for &key in black_box(&keys) {
+                *counts.get_or_insert_with(key, || 0) += 1;
+            }
+            counts
+        })
+    });
+    group.bench_function("entry", |b| {
+        b.iter(|| {
+            let mut counts = DirectIdMap::<u32, u32>::new();
+            for &key in black_box(&keys) {
+                *counts.entry(key).or_insert_with(|| 0) += 1;
+            }
+            counts
+        })
+    });
+    group.finish();
+}
+
+fn bench_maps(c: &mut Criterion) {
+    bench_map::<DirectIdMap<u32, u32>>(c);
+    bench_map::<HashMap<u32, u32>>(c);
+    bench_map::<Vec<Option<u32>>>(c);
+}
+
+criterion_group!(
+    benches,
+    bench_maps,
+    bench_bulk_load,
+    bench_map_values,
+    bench_get_or_insert
+);
+criterion_main!(benches);
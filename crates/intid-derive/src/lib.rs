@@ -52,6 +52,11 @@ fn impl_integer_id(ast: &DeriveInput) -> syn::Result<TokenStream> {
                         .clone()
                         .map_or_else(|| Member::from(0), Member::from);
                     let field_type = &field.ty;
+                    // Use tuple syntax for tuple structs, avoiding `clippy::init_numbered_fields`
+                    let construct = |value: TokenStream| match field.ident {
+                        Some(ref ident) => quote!(#name { #ident: #value }),
+                        None => quote!(#name(#value)),
+                    };
                     let field_type_as_id = quote_spanned! {
                         field_type.span() => <#field_type as intid::IntegerId>
                     };
@@ -61,11 +66,9 @@ fn impl_integer_id(ast: &DeriveInput) -> syn::Result<TokenStream> {
                     let int_constructor = |method_name: &str, needs_try: bool| {
                         let maybe_try = if needs_try { quote!(?) } else { quote!() };
                         let method_name = Ident::new(method_name, field.ty.span());
-                        quote_spanned! {
-                            field_type.span() => #name {
-                                #field_name: #field_type_as_id::#method_name(int)#maybe_try
-                            }
-                        }
+                        construct(quote_spanned! {
+                            field_type.span() => #field_type_as_id::#method_name(int)#maybe_try
+                        })
                     };
                     let impl_from_int = int_constructor("from_int", false);
                     let impl_from_int_checked = int_constructor("from_int_checked", true);
@@ -74,29 +77,30 @@ fn impl_integer_id(ast: &DeriveInput) -> syn::Result<TokenStream> {
                     let impl_decl =
                         quote_spanned! { name.span() => impl intid::IntegerId for #name };
                     let contiguous_impl = if let Some(contiguous) = options.contiguous {
+                        let min_id =
+                            construct(quote!(<#field_type as intid::ContiguousIntegerId>::MIN_ID));
+                        let max_id =
+                            construct(quote!(<#field_type as intid::ContiguousIntegerId>::MAX_ID));
                         quote_spanned! {
                             contiguous =>
                             #[automatically_derived]
                             impl intid::ContiguousIntegerId for #name {
-                                const MIN_ID: Self = #name {
-                                    #field_name: <#field_type as intid::ContiguousIntegerId>::MIN_ID,
-                                };
-                                const MAX_ID: Self = #name {
-                                    #field_name: <#field_type as intid::ContiguousIntegerId>::MIN_ID,
-                                };
+                                const MIN_ID: Self = #min_id;
+                                const MAX_ID: Self = #max_id;
                             }
                         }
                     } else {
                         quote!()
                     };
                     let counter_impl = if let Some(counter) = options.counter {
+                        let start =
+                            construct(quote!(<#field_type as intid::IntegerIdCounter>::START));
                         quote_spanned! {
                             counter =>
                             #[automatically_derived]
                             impl intid::IntegerIdCounter for #name {
-                                const START: Self = #name {
-                                    #field_name: <#field_type as intid::IntegerIdCounter>::START,
-                                };
+                                const START: Self = #start;
+                                const START_INT: #int_type = <#field_type as intid::IntegerIdCounter>::START_INT;
                             }
                         }
                     } else {
@@ -11,6 +11,11 @@ pub use self::map::DirectIdMap;
 pub use self::set::DirectIdSet;
 use intid::uint::UnsignedPrimInt;
 
+/// The type of a word in a [`fixedbitset::FixedBitSet`].
+type Word = fixedbitset::Block;
+/// The number of ids stored in each [`Word`].
+const WORD_BITS: usize = Word::BITS as usize;
+
 /// Panic indicating that an id would exhaust available memory.
 #[inline(never)]
 #[track_caller]
@@ -21,3 +26,14 @@ fn oom_id(id: impl UnsignedPrimInt) -> ! {
         intid::uint::debug_desc(id),
     )
 }
+
+/// The number of slots needed to store every id up to and including `max_id`.
+///
+/// Panics if this would exhaust memory.
+#[inline]
+#[track_caller]
+fn slots_for_max_id<T: UnsignedPrimInt>(max_id: T) -> usize {
+    intid::uint::to_usize_checked(max_id)
+        .and_then(|index| index.checked_add(1))
+        .unwrap_or_else(|| oom_id(max_id))
+}
@@ -1,16 +1,28 @@
 //! Implements [`DirectIdMap`], a thin wrapper over a [`Vec<Option<T>>`].
 
-use crate::direct::oom_id;
+use crate::direct::{oom_id, slots_for_max_id, DirectIdSet, Word, WORD_BITS};
+use alloc::collections::{BTreeMap, TryReserveError};
 use core::fmt::{Debug, Formatter};
+use core::hash::{BuildHasher, Hash, Hasher};
 use core::marker::PhantomData;
-use core::ops::{Index, IndexMut};
+use core::ops::{Bound, Index, IndexMut, RangeBounds};
 use intid::{EquivalentId, IntegerId};
+use std::collections::HashMap;
+
+mod cursor;
+mod default;
+mod entry;
+mod error;
+
+pub use self::cursor::CursorMut;
+pub use self::default::DefaultDirectIdMap;
+pub use self::entry::{
+    Entry, OccupiedEntry, OccupiedError, RawEntryMut, RawVacantEntry, VacantEntry,
+};
+pub use self::error::{DuplicateKeyError, InvalidIdError, RenameError, UnsortedKeyError};
 
 /// A map implemented as a [`Vec<Option<T>>`],
 /// which takes space proportional to the size of the maximum id.
-///
-/// There is no entry API because the overhead of lookups is very small.
-#[derive(Clone)]
 pub struct DirectIdMap<K: IntegerId, V> {
     // Optimization idea: If `Option<V>` does not support the nullable-pointer optimization,
     // fallback to using a bitset + MaybeUninit.
@@ -19,6 +31,23 @@ pub struct DirectIdMap<K: IntegerId, V> {
     len: usize,
     marker: PhantomData<K>,
 }
+impl<K: IntegerId, V: Clone> Clone for DirectIdMap<K, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        DirectIdMap {
+            values: self.values.clone(),
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    /// Reuse the existing allocation when cloning into an existing map.
+    #[inline]
+    fn clone_from(&mut self, source: &Self) {
+        self.values.clone_from(&source.values);
+        self.len = source.len;
+    }
+}
 impl<K: IntegerId, V> Default for DirectIdMap<K, V> {
     #[inline]
     fn default() -> Self {
@@ -27,6 +56,8 @@ impl<K: IntegerId, V> Default for DirectIdMap<K, V> {
 }
 impl<K: IntegerId, V> DirectIdMap<K, V> {
     /// Create a new map with no entries.
+    ///
+    /// This does not allocate until the first entry is inserted.
     #[inline]
     pub const fn new() -> Self {
         DirectIdMap {
@@ -35,6 +66,205 @@ impl<K: IntegerId, V> DirectIdMap<K, V> {
             marker: PhantomData,
         }
     }
+
+    /// Create an empty map with space for the specified number of ids.
+    ///
+    /// Since this is a direct map,
+    /// this hints at the maximum valid id and not the length.
+    /// A capacity of zero does not allocate.
+    #[inline]
+    pub fn with_capacity(max_id: usize) -> Self {
+        DirectIdMap {
+            values: Vec::with_capacity(max_id),
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Create a map from a table of slots, indexed by the integer id of each key.
+    ///
+    /// This is the inverse of [`Self::into_raw_slots`],
+    /// and reuses the allocation of the table.
+    ///
+    /// ## Errors
+    /// If an occupied slot does not correspond to a valid key,
+    /// returning the first such slot.
+    pub fn from_raw_slots(values: Vec<Option<V>>) -> Result<Self, InvalidIdError<K>> {
+        let mut len = 0;
+        for (index, entry) in values.iter().enumerate() {
+            if entry.is_none() {
+                continue;
+            }
+            let valid = intid::uint::from_usize_checked::<K::Int>(index)
+                .and_then(K::from_int_checked)
+                .is_some();
+            if !valid {
+                return Err(InvalidIdError::new(index));
+            }
+            len += 1;
+        }
+        Ok(DirectIdMap {
+            values,
+            len,
+            marker: PhantomData,
+        })
+    }
+
+    /// Convert the map into its underlying table of slots,
+    /// indexed by the integer id of each key.
+    ///
+    /// The table may have trailing empty slots.
+    /// See [`Self::as_slots`] to borrow the table instead.
+    #[inline]
+    pub fn into_raw_slots(self) -> Vec<Option<V>> {
+        self.values
+    }
+
+    /// Convert a dense map into a vector of its values, indexed by id.
+    ///
+    /// This is the inverse of [`Self::from_dense_vec`].
+    /// When `Option<V>` has the same layout as `V`,
+    /// the standard library may reuse the allocation,
+    /// but this is an optimization rather than a guarantee.
+    ///
+    /// ## Errors
+    /// If the map is not [dense](Self::is_dense),
+    /// it is returned unchanged.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let dense: DirectIdMap<u32, &str> = direct_idmap! { 0 => "foo", 1 => "bar" };
+    /// assert_eq!(dense.into_dense_vec(), Ok(vec!["foo", "bar"]));
+    /// let sparse: DirectIdMap<u32, &str> = direct_idmap! { 1 => "bar" };
+    /// assert!(sparse.into_dense_vec().is_err());
+    /// ```
+    pub fn into_dense_vec(self) -> Result<Vec<V>, Self> {
+        if !self.is_dense() {
+            return Err(self);
+        }
+        let mut values = self.values;
+        values.truncate(self.len);
+        // dense, so every remaining slot is occupied
+        Ok(values.into_iter().map(Option::unwrap).collect())
+    }
+
+    /// Create a map with space for every id up to and including that of `key`.
+    ///
+    /// Since this is a direct map,
+    /// this avoids the ambiguity of capacity meaning the maximum id rather than the length.
+    ///
+    /// ## Panics
+    /// If the id is so large that the map would exhaust memory.
+    #[inline]
+    #[track_caller]
+    pub fn with_max_key(key: K) -> Self {
+        Self::with_capacity(slots_for_max_id(key.to_int()))
+    }
+
+    /// Create a map from entries which are sorted by strictly increasing key.
+    ///
+    /// This avoids the per-key lookup of [`FromIterator`],
+    /// instead appending each value to the end of the table.
+    /// The table grows with amortized reallocation,
+    /// since the number of entries says little about the maximum id.
+    /// Returns an error if a key is not greater than the key before it,
+    /// which includes duplicate keys.
+    pub fn from_sorted_iter(
+        iter: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<Self, UnsortedKeyError<K>> {
+        let iter = iter.into_iter();
+        let mut values = Vec::new();
+        let mut previous: Option<K> = None;
+        let mut len = 0;
+        for (key, value) in iter {
+            let id = key.to_int();
+            let index = intid::uint::to_usize_checked(id).unwrap_or_else(|| oom_id(id));
+            if let Some(previous) = previous {
+                if index < values.len() {
+                    return Err(UnsortedKeyError { key, previous });
+                }
+            }
+            if index > values.len() {
+                values.resize_with(index, || None);
+            }
+            values.push(Some(value));
+            previous = Some(key);
+            len += 1;
+        }
+        Ok(DirectIdMap {
+            values,
+            len,
+            marker: PhantomData,
+        })
+    }
+
+    /// Create a map with a [`V::default()`](Default::default) value for each of the given keys.
+    ///
+    /// Duplicate keys result in a single entry.
+    /// This is a named constructor rather than a [`FromIterator`] implementation,
+    /// since that would conflict with collecting key-value pairs.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::DirectIdMap;
+    /// let map = DirectIdMap::<u32, Vec<&str>>::from_keys(0..3);
+    /// assert_eq!(map.len(), 3);
+    /// assert!(map[2].is_empty());
+    /// ```
+    pub fn from_keys(keys: impl IntoIterator<Item = K>) -> Self
+    where
+        V: Default,
+    {
+        let mut res = Self::new();
+        for key in keys {
+            res.get_or_insert_default(key);
+        }
+        res
+    }
+
+    /// Create a map from a vector of values, using each index as the id of its key.
+    ///
+    /// Every element becomes an entry, so the map is completely dense.
+    /// When `Option<V>` has the same layout as `V`,
+    /// the standard library may reuse the allocation,
+    /// but this is an optimization rather than a guarantee.
+    ///
+    /// ## Panics
+    /// If an index is not a valid id for the key type.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::DirectIdMap;
+    /// let map = DirectIdMap::<u32, _>::from_dense_vec(vec!["foo", "bar"]);
+    /// assert_eq!(map[1], "bar");
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    pub fn from_dense_vec(values: Vec<V>) -> Self {
+        for index in 0..values.len() {
+            let valid = intid::uint::from_usize_checked::<K::Int>(index)
+                .and_then(K::from_int_checked)
+                .is_some();
+            assert!(valid, "index {index} is not a valid id");
+        }
+        DirectIdMap {
+            len: values.len(),
+            values: values.into_iter().map(Some).collect(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Create a map from entries in any order,
+    /// sizing the table to the specified maximum id up front.
+    fn from_unsorted(entries: impl IntoIterator<Item = (K, V)>, max_id: Option<K::Int>) -> Self {
+        let mut res = Self::new();
+        if let Some(max_id) = max_id {
+            res.grow_to(intid::uint::to_usize_checked(max_id).unwrap_or_else(|| oom_id(max_id)));
+        }
+        res.extend(entries);
+        res
+    }
+
     /// The number of entries in the map.
     #[inline]
     pub fn len(&self) -> usize {
@@ -47,6 +277,73 @@ impl<K: IntegerId, V> DirectIdMap<K, V> {
         self.len == 0
     }
 
+    /// The number of ids the map can hold without reallocating.
+    ///
+    /// Since this is a direct map,
+    /// this refers to the maximum id rather than the number of entries.
+    /// Any key whose integer value is less than the capacity can be inserted without allocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.values.capacity()
+    }
+
+    /// The number of bytes of heap memory allocated by the map.
+    ///
+    /// This does not include any memory owned by the values themselves,
+    /// only the memory used for the table of slots.
+    #[inline]
+    pub fn allocated_bytes(&self) -> usize {
+        self.values.capacity() * core::mem::size_of::<Option<V>>()
+    }
+
+    /// Reserve capacity for at least `additional` more ids
+    /// past the slots currently in use.
+    ///
+    /// Since this is a direct map,
+    /// capacity refers to the maximum id rather than the number of entries.
+    /// Like [`Vec::reserve`], this may over-allocate to amortize future growth.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.values.reserve(additional);
+    }
+
+    /// Reserve the minimum capacity for `additional` more ids
+    /// past the slots currently in use.
+    ///
+    /// Unlike [`Self::reserve`], this avoids over-allocation,
+    /// which is useful when the final maximum id is known in advance.
+    #[inline]
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.values.reserve_exact(additional);
+    }
+
+    /// Try to reserve capacity for at least `additional` more ids
+    /// past the slots currently in use.
+    ///
+    /// Like [`Self::reserve`], except allocation failure is returned as an error
+    /// rather than aborting the process.
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.values.try_reserve(additional)
+    }
+
+    /// Try to reserve capacity for every id up to and including that of `key`,
+    /// so that it can be inserted without allocating.
+    ///
+    /// This is useful when keys come from untrusted input,
+    /// since an absurdly large id is returned as an error
+    /// rather than panicking or aborting the process.
+    pub fn try_reserve_for_key(&mut self, key: K) -> Result<(), TryReserveError> {
+        let id = key.to_int();
+        match intid::uint::to_usize_checked(id).and_then(|index| index.checked_add(1)) {
+            Some(slots) => self
+                .values
+                .try_reserve(slots.saturating_sub(self.values.len())),
+            // more slots than fit in the address space
+            None => Vec::<u8>::new().try_reserve(usize::MAX),
+        }
+    }
+
     /// Clear all entries in the map.
     #[inline]
     pub fn clear(&mut self) {
@@ -56,31 +353,142 @@ impl<K: IntegerId, V> DirectIdMap<K, V> {
 
     /// Trim unused capacity.
     pub fn shrink_to_fit(&mut self) {
+        self.trim_trailing_holes();
+        self.values.shrink_to_fit();
+    }
+
+    /// Trim unused capacity, while keeping room for ids up to `min_capacity`.
+    ///
+    /// Since this is a direct map,
+    /// capacity refers to the maximum id rather than the number of entries.
+    /// Existing entries are always kept, even if they exceed the minimum capacity.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.trim_trailing_holes();
+        self.values.shrink_to(min_capacity);
+    }
+
+    /// Remove trailing empty slots, which occupy space without holding any entries.
+    fn trim_trailing_holes(&mut self) {
         while matches!(self.values.last(), Some(None)) {
             self.values.pop();
         }
-        self.values.shrink_to_fit();
     }
 
     /// Get the value associated with the specified key, or `None` if missing.
     #[inline]
     pub fn get(&self, id: impl EquivalentId<K>) -> Option<&V> {
-        let id = id.as_id();
-        self.values
-            .get(intid::uint::to_usize_checked(id.to_int())?)?
-            .as_ref()
+        self.get_by_id(id.as_id().to_int())
     }
 
     /// Get a mutable reference to the value associated with the specified key,
     /// or `None` if missing.
     #[inline]
     pub fn get_mut(&mut self, id: impl EquivalentId<K>) -> Option<&mut V> {
-        let id = id.as_id();
+        self.get_by_id_mut(id.as_id().to_int())
+    }
+
+    /// Check if the map contains the specified key.
+    #[inline]
+    pub fn contains_key(&self, id: impl EquivalentId<K>) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Check if the map contains every key in the specified set.
+    ///
+    /// This is `true` for an empty set.
+    #[inline]
+    pub fn contains_all(&self, keys: &DirectIdSet<K>) -> bool {
+        keys.len() <= self.len
+            && keys
+                .bits()
+                .ones()
+                .all(|index| matches!(self.values.get(index), Some(Some(_))))
+    }
+
+    /// Check if the map contains any key in the specified set.
+    ///
+    /// This is `false` for an empty set.
+    #[inline]
+    pub fn contains_any(&self, keys: &DirectIdSet<K>) -> bool {
+        keys.bits()
+            .ones()
+            .take_while(|&index| index < self.values.len())
+            .any(|index| self.values[index].is_some())
+    }
+
+    /// Get the value associated with the specified integer id, or `None` if missing.
+    ///
+    /// This avoids constructing a key, so it never panics for invalid or out of range ids.
+    #[inline]
+    pub fn get_by_id(&self, id: K::Int) -> Option<&V> {
         self.values
-            .get_mut(intid::uint::to_usize_checked(id.to_int())?)?
+            .get(intid::uint::to_usize_checked(id)?)?
+            .as_ref()
+    }
+
+    /// Get a mutable reference to the value associated with the specified integer id,
+    /// or `None` if missing.
+    ///
+    /// This avoids constructing a key, so it never panics for invalid or out of range ids.
+    #[inline]
+    pub fn get_by_id_mut(&mut self, id: K::Int) -> Option<&mut V> {
+        self.values
+            .get_mut(intid::uint::to_usize_checked(id)?)?
             .as_mut()
     }
 
+    /// Check if the map contains a key with the specified integer id.
+    ///
+    /// This avoids constructing a key, so it never panics for invalid or out of range ids.
+    #[inline]
+    pub fn contains_id(&self, id: K::Int) -> bool {
+        self.get_by_id(id).is_some()
+    }
+
+    /// Get the key and value associated with the specified key, or `None` if missing.
+    ///
+    /// Since an [`IntegerId`] carries no information beyond its integer value,
+    /// the returned key is always equal to the one stored in the map.
+    #[inline]
+    pub fn get_key_value(&self, id: impl EquivalentId<K>) -> Option<(K, &V)> {
+        let key = id.as_id();
+        self.get(key).map(|value| (key, value))
+    }
+
+    /// Get the key and a mutable reference to the value associated with the specified key,
+    /// or `None` if missing.
+    #[inline]
+    pub fn get_key_value_mut(&mut self, id: impl EquivalentId<K>) -> Option<(K, &mut V)> {
+        let key = id.as_id();
+        self.get_mut(key).map(|value| (key, value))
+    }
+
+    /// Get mutable references to the values associated with several distinct keys at once.
+    ///
+    /// Returns `None` if any of the keys are missing,
+    /// or if the same key is specified more than once.
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        ids: [impl EquivalentId<K>; N],
+    ) -> Option<[&mut V; N]> {
+        let mut indexes = [0usize; N];
+        for (i, id) in ids.iter().enumerate() {
+            let index = intid::uint::to_usize_checked(id.as_id().to_int())?;
+            if !matches!(self.values.get(index), Some(Some(_))) || indexes[..i].contains(&index) {
+                return None;
+            }
+            indexes[i] = index;
+        }
+        let base = self.values.as_mut_ptr();
+        Some(indexes.map(|index| {
+            // SAFETY: Index was bounds checked above
+            let entry = unsafe { base.add(index) };
+            // SAFETY: Indexes are distinct, so the resulting references are disjoint
+            let entry = unsafe { &mut *entry };
+            entry.as_mut().unwrap()
+        }))
+    }
+
     /// Insert a key and a value, returning the previous value.
     #[inline]
     pub fn insert(&mut self, id: K, value: V) -> Option<V> {
@@ -94,193 +502,1906 @@ impl<K: IntegerId, V> DirectIdMap<K, V> {
         old_value
     }
 
-    /// Remove a value associated with the given,
-    /// returning the previous value ifp resent.
+    /// Insert a key and a value, returning a mutable reference to the new value.
+    ///
+    /// Any previous value is overwritten and dropped.
+    /// Unlike [`Self::get_or_insert`], this always stores the specified value.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::DirectIdMap;
+    /// let mut names = DirectIdMap::<u32, Vec<&str>>::new();
+    /// names.insert_and_get(3, vec!["foo"]).push("bar");
+    /// assert_eq!(names[3], ["foo", "bar"]);
+    /// names.insert_and_get(3, Vec::new()).push("baz");
+    /// assert_eq!(names[3], ["baz"]);
+    /// ```
     #[inline]
-    pub fn remove(&mut self, id: impl EquivalentId<K>) -> Option<V> {
-        let id = id.as_id().to_int();
-        let id = intid::uint::to_usize_checked(id).unwrap_or_else(|| oom_id(id));
-        if id >= self.values.len() {
-            return None;
-        }
-        let old_value = self.values[id].take();
-        if old_value.is_some() {
-            self.len -= 1;
+    pub fn insert_and_get(&mut self, key: K, value: V) -> &mut V {
+        let id = key.to_int();
+        let index = intid::uint::to_usize_checked(id).unwrap_or_else(|| oom_id(id));
+        self.grow_to(index);
+        let slot = &mut self.values[index];
+        if slot.is_none() {
+            self.len += 1;
         }
-        old_value
+        slot.insert(value)
     }
 
+    /// Get a mutable reference to the value associated with the key,
+    /// inserting the specified value if it is missing.
     #[inline]
-    fn grow_to(&mut self, max_id: usize) {
-        if self.values.len() <= max_id {
-            self.grow_fallback(max_id);
+    pub fn get_or_insert(&mut self, key: K, value: V) -> &mut V {
+        self.get_or_insert_with(key, || value)
+    }
+
+    /// Get a mutable reference to the value associated with the key,
+    /// inserting the result of the function if it is missing.
+    ///
+    /// This is equivalent to `map.entry(key).or_insert_with(func)`,
+    /// but directly accesses the slot without constructing an [`Entry`].
+    #[inline]
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, func: F) -> &mut V {
+        let id = key.to_int();
+        let index = intid::uint::to_usize_checked(id).unwrap_or_else(|| oom_id(id));
+        if index >= self.values.len() {
+            self.grow_fallback(index);
         }
+        let DirectIdMap { values, len, .. } = self;
+        let slot = &mut values[index];
+        if slot.is_some() {
+            return slot.as_mut().unwrap();
+        }
+        // call the function before updating the length, in case it panics
+        let value = slot.insert(func());
+        *len += 1;
+        value
     }
-    #[cold]
-    fn grow_fallback(&mut self, max_id: usize) {
-        // amortized growth
-        let new_len = core::cmp::max(
-            self.len().checked_mul(2).expect("capacity overflow"),
-            max_id.checked_add(1).unwrap_or_else(|| oom_id(max_id)),
-        );
-        assert!(new_len >= self.values.len());
-        assert!(new_len > max_id);
-        self.values.resize_with(new_len, || None);
+
+    /// Get a mutable reference to the value associated with the key,
+    /// inserting [`V::default()`](Default::default) if it is missing.
+    ///
+    /// This is equivalent to `map.entry(key).or_default()`,
+    /// but directly accesses the slot without constructing an [`Entry`].
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::DirectIdMap;
+    /// let mut counts = DirectIdMap::<u32, u32>::new();
+    /// for id in [3, 7, 3] {
+    ///     *counts.get_or_insert_default(id) += 1;
+    /// }
+    /// assert_eq!(counts[3], 2);
+    /// assert_eq!(counts[7], 1);
+    /// ```
+    #[inline]
+    pub fn get_or_insert_default(&mut self, key: K) -> &mut V
+    where
+        V: Default,
+    {
+        self.get_or_insert_with(key, V::default)
     }
 
-    /// Iterate over the key-value pairs in the map.
+    /// Insert a key and value without checking if the key is already present,
+    /// returning the key and a mutable reference to the value.
     ///
-    /// Guaranteed to be sorted by the integer id of the key.
+    /// This is useful for bulk-loading keys which are known to be unique,
+    /// avoiding the need to return the previous value.
+    ///
+    /// The key must not already be present in the map.
+    /// Otherwise, the old value is dropped and the length of the map becomes incorrect.
+    /// This is a logic error rather than undefined behavior,
+    /// and is checked when debug assertions are enabled.
     #[inline]
-    pub fn iter(&self) -> Iter<'_, K, V> {
-        Iter {
-            marker: PhantomData,
-            len: self.len,
-            source: self.values.iter().enumerate(),
-        }
+    pub fn insert_unique_unchecked(&mut self, key: K, value: V) -> (K, &mut V) {
+        let id = key.to_int();
+        let index = intid::uint::to_usize_checked(id).unwrap_or_else(|| oom_id(id));
+        self.grow_to(index);
+        let entry = &mut self.values[index];
+        debug_assert!(entry.is_none(), "key already present: {key:?}");
+        self.len += 1;
+        (key, entry.insert(value))
     }
 
-    /// Mutably iterate over the key-value pairs in the map.
+    /// Insert a key and value if the key is not already present,
+    /// returning a mutable reference to the inserted value.
     ///
-    /// Guaranteed to be sorted by the integer id of the key.
+    /// If the key is already present, nothing is updated
+    /// and an error containing the occupied entry and the rejected value is returned.
     #[inline]
-    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
-        IterMut {
-            marker: PhantomData,
-            len: self.len,
-            source: self.values.iter_mut().enumerate(),
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<&mut V, OccupiedError<'_, K, V>> {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value }),
+            Entry::Vacant(entry) => Ok(entry.insert(value)),
         }
     }
 
-    /// Iterate over the entries in the map,
-    /// removing entries when the callback returns false.
-    ///
-    /// See also [std::collections::HashMap::retain].
-    pub fn retain(&mut self, mut func: impl FnMut(K, &mut V) -> bool) {
-        for (index, entry) in self.values.iter_mut().enumerate() {
-            if entry.is_none() {
-                continue;
-            }
-            // SAFETY: If entry exists, the key is guaranteed to be valid
-            let key = unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) };
-            if !func(key, entry.as_mut().unwrap()) {
-                *entry = None;
-                self.len -= 1;
+    /// Get the entry associated with the specified key,
+    /// for in-place manipulation.
+    #[inline]
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        match intid::uint::to_usize_checked(key.to_int()) {
+            Some(index) if matches!(self.values.get(index), Some(Some(_))) => {
+                Entry::Occupied(OccupiedEntry {
+                    key,
+                    slot: &mut self.values[index],
+                    len: &mut self.len,
+                })
             }
+            _ => Entry::Vacant(VacantEntry { key, map: self }),
         }
     }
-}
-impl<K: IntegerId, V: PartialEq> PartialEq for DirectIdMap<K, V> {
-    fn eq(&self, other: &Self) -> bool {
-        self.len == other.len && self.values == other.values
-    }
-}
-impl<K: IntegerId, V: Eq> Eq for DirectIdMap<K, V> {}
-impl<K: IntegerId, V> Index<K> for DirectIdMap<K, V> {
-    type Output = V;
 
+    /// Get the entry associated with the specified integer id,
+    /// without needing to construct a key.
+    ///
+    /// This is useful when the id may not correspond to a valid key.
+    /// Inserting into a vacant entry requires a key with the same id.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::direct::map::RawEntryMut;
+    /// # use idmap::DirectIdMap;
+    /// let mut map = DirectIdMap::<u32, &str>::new();
+    /// match map.raw_entry_mut(3) {
+    ///     RawEntryMut::Occupied(_) => unreachable!(),
+    ///     RawEntryMut::Vacant(entry) => {
+    ///         entry.insert(3, "foo");
+    ///     }
+    /// }
+    /// assert_eq!(map[3], "foo");
+    /// ```
     #[inline]
-    #[track_caller]
-    fn index(&self, index: K) -> &Self::Output {
-        self.get(index).expect("index out of bounds")
+    pub fn raw_entry_mut(&mut self, id: K::Int) -> RawEntryMut<'_, K, V> {
+        match intid::uint::to_usize_checked(id) {
+            Some(index) if matches!(self.values.get(index), Some(Some(_))) => {
+                // SAFETY: If entry exists, the key is guaranteed to be valid
+                let key = unsafe { K::from_int_unchecked(id) };
+                RawEntryMut::Occupied(OccupiedEntry {
+                    key,
+                    slot: &mut self.values[index],
+                    len: &mut self.len,
+                })
+            }
+            _ => RawEntryMut::Vacant(RawVacantEntry { id, map: self }),
+        }
     }
-}
-impl<K: IntegerId, V> IndexMut<K> for DirectIdMap<K, V> {
+
+    /// Get a cursor positioned at the first entry of the map,
+    /// which can remove entries while iterating.
+    ///
+    /// Entries are visited in order of the integer id of the key.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let mut map: DirectIdMap<u32, u32> = direct_idmap! { 1 => 10, 2 => 15, 4 => 20 };
+    /// let mut cursor = map.cursor_mut();
+    /// while let Some((_, value)) = cursor.current() {
+    ///     if *value % 10 != 0 {
+    ///         cursor.remove_current();
+    ///     } else {
+    ///         *value += 1;
+    ///         cursor.advance();
+    ///     }
+    /// }
+    /// assert_eq!(map, direct_idmap! { 1 => 11, 4 => 21 });
+    /// ```
     #[inline]
-    #[track_caller]
-    fn index_mut(&mut self, index: K) -> &mut Self::Output {
-        self.get_mut(index).expect("index out of bounds")
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, K, V> {
+        CursorMut::new(self)
     }
-}
-impl<'a, K: IntegerId, V> Index<&'a K> for DirectIdMap<K, V> {
-    type Output = V;
 
+    /// The key with the smallest id which is not present in the map,
+    /// or `None` if that id is not a valid key.
+    ///
+    /// This is zero for an empty map, and one past the largest key for a dense map.
+    /// It takes time proportional to the number of entries before the first vacant slot.
     #[inline]
+    pub fn vacant_key(&self) -> Option<K> {
+        let index = self
+            .values
+            .iter()
+            .position(Option::is_none)
+            .unwrap_or(self.values.len());
+        intid::uint::from_usize_checked(index).and_then(K::from_int_checked)
+    }
+
+    /// Insert a value using the key returned by [`Self::vacant_key`],
+    /// returning the key.
+    ///
+    /// This is useful for using the map as a slab, reusing the ids of removed entries.
+    ///
+    /// ## Panics
+    /// If there is no vacant key.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::DirectIdMap;
+    /// let mut slab = DirectIdMap::<u32, &str>::new();
+    /// let foo = slab.insert_vacant_value("foo");
+    /// let bar = slab.insert_vacant_value("bar");
+    /// assert_eq!((foo, bar), (0, 1));
+    /// slab.remove(foo);
+    /// assert_eq!(slab.insert_vacant_value("baz"), 0);
+    /// ```
     #[track_caller]
-    fn index(&self, index: &'a K) -> &Self::Output {
-        self.get(*index).expect("index out of bounds")
+    pub fn insert_vacant_value(&mut self, value: V) -> K {
+        let key = self.vacant_key().expect("no vacant key");
+        self.insert(key, value);
+        key
+    }
+
+    /// The integer id of the smallest key in the map,
+    /// or `None` if the map is empty.
+    ///
+    /// This takes time proportional to the number of empty slots before the smallest key.
+    #[inline]
+    pub fn min_id(&self) -> Option<K::Int> {
+        self.values
+            .iter()
+            .position(Option::is_some)
+            .map(intid::uint::from_usize_wrapping)
+    }
+
+    /// The integer id of the largest key in the map,
+    /// or `None` if the map is empty.
+    ///
+    /// This takes time proportional to the number of empty slots after the largest key,
+    /// which are left behind when the largest keys are removed.
+    #[inline]
+    pub fn max_id(&self) -> Option<K::Int> {
+        self.values
+            .iter()
+            .rposition(Option::is_some)
+            .map(intid::uint::from_usize_wrapping)
+    }
+
+    /// The entry with the smallest key, or `None` if the map is empty.
+    ///
+    /// This takes time proportional to the number of empty slots before the smallest key.
+    #[inline]
+    pub fn first_key_value(&self) -> Option<(K, &V)> {
+        self.iter().next()
+    }
+
+    /// The entry with the largest key, or `None` if the map is empty.
+    ///
+    /// This takes time proportional to the number of empty slots after the largest key.
+    #[inline]
+    pub fn last_key_value(&self) -> Option<(K, &V)> {
+        self.iter().next_back()
+    }
+
+    /// A mutable reference to the value with the smallest key,
+    /// or `None` if the map is empty.
+    ///
+    /// This takes time proportional to the number of empty slots before the smallest key.
+    #[inline]
+    pub fn first_value_mut(&mut self) -> Option<&mut V> {
+        self.values_mut().next()
+    }
+
+    /// A mutable reference to the value with the largest key,
+    /// or `None` if the map is empty.
+    ///
+    /// This takes time proportional to the number of empty slots after the largest key.
+    #[inline]
+    pub fn last_value_mut(&mut self) -> Option<&mut V> {
+        self.values_mut().next_back()
+    }
+
+    /// The entry with the smallest key, for in-place manipulation,
+    /// or `None` if the map is empty.
+    ///
+    /// This takes time proportional to the number of empty slots before the smallest key.
+    #[inline]
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, K, V>> {
+        let index = self.values.iter().position(Option::is_some)?;
+        Some(self.occupied_entry_at(index))
+    }
+
+    /// The entry with the largest key, for in-place manipulation,
+    /// or `None` if the map is empty.
+    ///
+    /// This takes time proportional to the number of empty slots after the largest key.
+    #[inline]
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, K, V>> {
+        let index = self.values.iter().rposition(Option::is_some)?;
+        Some(self.occupied_entry_at(index))
+    }
+
+    /// Get the entry for an index which is known to be occupied.
+    #[inline]
+    fn occupied_entry_at(&mut self, index: usize) -> OccupiedEntry<'_, K, V> {
+        debug_assert!(self.values[index].is_some());
+        // SAFETY: If entry exists, the key is guaranteed to be valid
+        let key = unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) };
+        OccupiedEntry {
+            key,
+            slot: &mut self.values[index],
+            len: &mut self.len,
+        }
+    }
+
+    /// Remove and return the entry with the largest key,
+    /// or `None` if the map is empty.
+    ///
+    /// Empty slots after the largest key are discarded,
+    /// but the allocated memory is retained.
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        while let Some(entry) = self.values.pop() {
+            if let Some(value) = entry {
+                self.len -= 1;
+                let index = self.values.len();
+                // SAFETY: If entry exists, the key is guaranteed to be valid
+                let key = unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) };
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    /// Remove and return the entry with the smallest key,
+    /// or `None` if the map is empty.
+    ///
+    /// This takes time proportional to the number of empty slots before the smallest key.
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let (index, entry) = self
+            .values
+            .iter_mut()
+            .enumerate()
+            .find(|(_, entry)| entry.is_some())?;
+        self.len -= 1;
+        // SAFETY: If entry exists, the key is guaranteed to be valid
+        let key = unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) };
+        entry.take().map(|value| (key, value))
+    }
+
+    /// Keep only the first `len` entries, ordered by the integer id of the key,
+    /// dropping the rest.
+    ///
+    /// Has no effect if `len` is greater than or equal to the current length.
+    /// The allocated memory is retained.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        let cut = self
+            .values
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.is_some())
+            .nth(len)
+            .map(|(index, _)| index)
+            .unwrap();
+        // update the length first, in case a destructor panics
+        self.len = len;
+        self.values.truncate(cut);
+    }
+
+    /// Split the map in two at the given key,
+    /// returning a new map containing every entry whose key is greater than or equal to `at`.
+    ///
+    /// The original map keeps its allocated capacity.
+    /// Since this is a direct map, the returned map uses space proportional to its largest key.
+    pub fn split_off(&mut self, at: impl EquivalentId<K>) -> Self {
+        let at = intid::uint::to_usize_checked(at.as_id().to_int())
+            .unwrap_or(usize::MAX)
+            .min(self.values.len());
+        let moved = self.values[at..].iter().filter(|e| e.is_some()).count();
+        if moved == 0 {
+            self.values.truncate(at);
+            return Self::new();
+        }
+        let mut values = Vec::with_capacity(self.values.len());
+        values.resize_with(at, || None);
+        values.extend(self.values.drain(at..));
+        self.len -= moved;
+        DirectIdMap {
+            values,
+            len: moved,
+            marker: PhantomData,
+        }
+    }
+
+    /// Move all entries from `other` into this map,
+    /// overwriting existing values on key collisions.
+    ///
+    /// Afterwards `other` is empty, but retains its allocated capacity.
+    pub fn append(&mut self, other: &mut Self) {
+        let end = other
+            .values
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |index| index + 1);
+        if self.values.len() < end {
+            self.values.resize_with(end, || None);
+        }
+        for (entry, value) in self.values.iter_mut().zip(other.values.drain(..end)) {
+            if let Some(value) = value {
+                if entry.replace(value).is_none() {
+                    self.len += 1;
+                }
+            }
+        }
+        other.values.clear();
+        other.len = 0;
+    }
+
+    /// Insert all the entries from `other`,
+    /// calling `resolve` to combine the values whenever a key is already present.
+    ///
+    /// The callback is given the key, the existing value, and the incoming value.
+    /// Each incoming key is only looked up once.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let mut totals: DirectIdMap<u32, u64> = direct_idmap! { 1 => 10, 2 => 5 };
+    /// let shard: DirectIdMap<u32, u64> = direct_idmap! { 2 => 7, 3 => 1 };
+    /// totals.merge_from(shard, |_id, total, count| *total += count);
+    /// assert_eq!(totals, direct_idmap! { 1 => 10, 2 => 12, 3 => 1 });
+    /// ```
+    pub fn merge_from<F: FnMut(K, &mut V, V)>(
+        &mut self,
+        other: impl IntoIterator<Item = (K, V)>,
+        mut resolve: F,
+    ) {
+        for (key, value) in other {
+            match self.entry(key) {
+                Entry::Occupied(mut entry) => resolve(key, entry.get_mut(), value),
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+            }
+        }
+    }
+
+    /// Swap the values associated with two keys,
+    /// returning whether both keys were present.
+    ///
+    /// If either key is missing, the map is left unchanged.
+    pub fn swap(&mut self, a: impl EquivalentId<K>, b: impl EquivalentId<K>) -> bool {
+        let (a, b) = (a.as_id().to_int(), b.as_id().to_int());
+        if !(self.contains_id(a) && self.contains_id(b)) {
+            return false;
+        }
+        // present, so the ids are in bounds
+        self.values.swap(
+            intid::uint::to_usize_wrapping(a),
+            intid::uint::to_usize_wrapping(b),
+        );
+        true
+    }
+
+    /// Move the value associated with `old` to the key `new`.
+    ///
+    /// Fails if `old` is missing or `new` is already present,
+    /// in which case the map is left unchanged.
+    /// Renaming a present key to itself succeeds without doing anything.
+    pub fn rename_key(&mut self, old: impl EquivalentId<K>, new: K) -> Result<(), RenameError<K>> {
+        let old = old.as_id();
+        if !self.contains_key(old) {
+            return Err(RenameError::MissingKey(old));
+        }
+        if old == new {
+            return Ok(());
+        }
+        if self.contains_key(new) {
+            return Err(RenameError::OccupiedKey(new));
+        }
+        let new_id = new.to_int();
+        let new_index = intid::uint::to_usize_checked(new_id).unwrap_or_else(|| oom_id(new_id));
+        self.grow_to(new_index);
+        // present, so the id is in bounds
+        let old_index = intid::uint::to_usize_wrapping(old.to_int());
+        self.values.swap(old_index, new_index);
+        Ok(())
+    }
+
+    /// Remove a value associated with the given,
+    /// returning the previous value ifp resent.
+    #[inline]
+    pub fn remove(&mut self, id: impl EquivalentId<K>) -> Option<V> {
+        let id = id.as_id().to_int();
+        let id = intid::uint::to_usize_checked(id).unwrap_or_else(|| oom_id(id));
+        if id >= self.values.len() {
+            return None;
+        }
+        let old_value = self.values[id].take();
+        if old_value.is_some() {
+            self.len -= 1;
+        }
+        old_value
+    }
+
+    /// Remove the entry associated with the given key,
+    /// returning the key and value if present.
+    ///
+    /// Since an [`IntegerId`] carries no information beyond its integer value,
+    /// the returned key is always equal to the one stored in the map.
+    #[inline]
+    pub fn remove_entry(&mut self, id: impl EquivalentId<K>) -> Option<(K, V)> {
+        let key = id.as_id();
+        self.remove(key).map(|value| (key, value))
+    }
+
+    /// Remove the entries associated with each of the given keys,
+    /// returning the removed entries.
+    ///
+    /// The result is in the same order as the keys, skipping any that are missing.
+    /// If a key is repeated, only its first occurrence finds an entry.
+    pub fn remove_all<Q: EquivalentId<K>>(
+        &mut self,
+        keys: impl IntoIterator<Item = Q>,
+    ) -> Vec<(K, V)> {
+        keys.into_iter()
+            .filter_map(|key| self.remove_entry(key))
+            .collect()
+    }
+
+    /// Remove the entries associated with each of the given keys,
+    /// moving them into a new map.
+    ///
+    /// Keys which are missing or repeated are skipped.
+    pub fn extract<Q: EquivalentId<K>>(&mut self, keys: impl IntoIterator<Item = Q>) -> Self {
+        let mut res = Self::new();
+        for key in keys {
+            if let Some((key, value)) = self.remove_entry(key) {
+                res.insert(key, value);
+            }
+        }
+        res
+    }
+
+    /// Apply a patch of upserts and deletions in a single pass,
+    /// returning how many entries were inserted, updated, and removed.
+    ///
+    /// An entry of `Some(value)` inserts or replaces the value,
+    /// while `None` removes the key, doing nothing if it is already missing.
+    /// Entries are applied in order, so later entries for the same key win.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::direct::map::PatchSummary;
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let mut map: DirectIdMap<u32, &str> = direct_idmap! { 1 => "a", 2 => "b" };
+    /// let summary = map.apply_patch([(1, None), (2, Some("c")), (3, Some("d"))]);
+    /// assert_eq!(map, direct_idmap! { 2 => "c", 3 => "d" });
+    /// assert_eq!(
+    ///     summary,
+    ///     PatchSummary { inserted: 1, updated: 1, removed: 1 }
+    /// );
+    /// ```
+    pub fn apply_patch(&mut self, patch: impl IntoIterator<Item = (K, Option<V>)>) -> PatchSummary {
+        let mut summary = PatchSummary::default();
+        for (key, change) in patch {
+            match change {
+                Some(value) => match self.insert(key, value) {
+                    Some(_) => summary.updated += 1,
+                    None => summary.inserted += 1,
+                },
+                None => {
+                    if self.remove(key).is_some() {
+                        summary.removed += 1;
+                    }
+                }
+            }
+        }
+        summary
+    }
+
+    #[inline]
+    fn grow_to(&mut self, max_id: usize) {
+        if self.values.len() <= max_id {
+            self.grow_fallback(max_id);
+        }
+    }
+    #[cold]
+    fn grow_fallback(&mut self, max_id: usize) {
+        // amortized growth
+        let new_len = core::cmp::max(
+            self.len().checked_mul(2).expect("capacity overflow"),
+            max_id.checked_add(1).unwrap_or_else(|| oom_id(max_id)),
+        );
+        assert!(new_len >= self.values.len());
+        assert!(new_len > max_id);
+        self.values.resize_with(new_len, || None);
+    }
+
+    /// Debug the map using the integer ids of the keys,
+    /// rather than their [`Debug`] implementation.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let map: DirectIdMap<u32, &str> = direct_idmap! { 1 => "foo", 7 => "bar" };
+    /// assert_eq!(format!("{:?}", map.debug_ids()), r#"{1: "foo", 7: "bar"}"#);
+    /// ```
+    #[inline]
+    pub fn debug_ids(&self) -> DebugIds<'_, K, V> {
+        DebugIds { map: self }
+    }
+
+    /// View the underlying table of slots, indexed by the integer id of each key.
+    ///
+    /// Empty slots are `None`, and the slice may have trailing empty slots.
+    /// This is useful for handing the values to code that works on plain slices.
+    ///
+    /// A mutable version is intentionally not provided,
+    /// since filling or clearing a slot would invalidate the length of the map
+    /// and could create entries for invalid keys.
+    /// Use [`Self::values_mut`] to modify values in place.
+    #[inline]
+    pub fn as_slots(&self) -> &[Option<V>] {
+        &self.values
+    }
+
+    /// Iterate over the key-value pairs in the map.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            marker: PhantomData,
+            len: self.len,
+            source: self.values.iter().enumerate(),
+        }
+    }
+
+    /// Mutably iterate over the key-value pairs in the map.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            marker: PhantomData,
+            len: self.len,
+            source: self.values.iter_mut().enumerate(),
+        }
+    }
+
+    /// Iterate over the keys present in both this map and `other`,
+    /// along with their values from each map.
+    ///
+    /// Both tables are walked together in lockstep, without any hashing or probing.
+    /// Guaranteed to be sorted by the integer id of the key.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let names: DirectIdMap<u32, &str> = direct_idmap! { 1 => "foo", 2 => "bar", 5 => "baz" };
+    /// let ages: DirectIdMap<u32, u32> = direct_idmap! { 2 => 7, 4 => 3, 5 => 12 };
+    /// assert_eq!(
+    ///     names.iter_intersection(&ages).collect::<Vec<_>>(),
+    ///     [(2, &"bar", &7), (5, &"baz", &12)],
+    /// );
+    /// ```
+    #[inline]
+    pub fn iter_intersection<'a, V2>(
+        &'a self,
+        other: &'a DirectIdMap<K, V2>,
+    ) -> Intersection<'a, K, V, V2> {
+        Intersection {
+            marker: PhantomData,
+            source: self.values.iter().zip(other.values.iter()).enumerate(),
+        }
+    }
+
+    /// Iterate over the keys present in both this map and `other`,
+    /// giving mutable access to the values in this map.
+    ///
+    /// See [`Self::iter_intersection`] for details.
+    #[inline]
+    pub fn iter_intersection_mut<'a, V2>(
+        &'a mut self,
+        other: &'a DirectIdMap<K, V2>,
+    ) -> IntersectionMut<'a, K, V, V2> {
+        IntersectionMut {
+            marker: PhantomData,
+            source: self.values.iter_mut().zip(other.values.iter()).enumerate(),
+        }
+    }
+
+    /// Call the function on each key present in both this map and `other`,
+    /// passing a mutable reference to the value in this map and the value in `other`.
+    ///
+    /// Both tables are walked together in lockstep,
+    /// and keys present in only one map are left untouched.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let mut totals: DirectIdMap<u32, u64> = direct_idmap! { 1 => 10, 2 => 5 };
+    /// let deltas: DirectIdMap<u32, u64> = direct_idmap! { 2 => 7, 3 => 1 };
+    /// totals.zip_mut_with(&deltas, |_, total, delta| *total += delta);
+    /// assert_eq!(totals, direct_idmap! { 1 => 10, 2 => 12 });
+    /// ```
+    #[inline]
+    pub fn zip_mut_with<V2>(
+        &mut self,
+        other: &DirectIdMap<K, V2>,
+        mut func: impl FnMut(K, &mut V, &V2),
+    ) {
+        for (key, value, other) in self.iter_intersection_mut(other) {
+            func(key, value, other);
+        }
+    }
+
+    /// Iterate over the keys present in this map but not in `other`.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    #[inline]
+    pub fn keys_difference<'a, V2>(
+        &'a self,
+        other: &'a DirectIdMap<K, V2>,
+    ) -> KeysDifference<'a, K, V, V2> {
+        KeysDifference {
+            marker: PhantomData,
+            source: self.values.iter().enumerate(),
+            other: &other.values,
+        }
+    }
+
+    /// Iterate over the keys present in exactly one of this map and `other`.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    #[inline]
+    pub fn keys_symmetric_difference<'a, V2>(
+        &'a self,
+        other: &'a DirectIdMap<K, V2>,
+    ) -> KeysSymmetricDifference<'a, K, V, V2> {
+        KeysSymmetricDifference {
+            marker: PhantomData,
+            indexes: 0..self.values.len().max(other.values.len()),
+            left: &self.values,
+            right: &other.values,
+        }
+    }
+
+    /// Check if this map has no keys in common with `other`.
+    #[inline]
+    pub fn is_disjoint<V2>(&self, other: &DirectIdMap<K, V2>) -> bool {
+        self.iter_intersection(other).next().is_none()
+    }
+
+    /// Check if every key in this map is also present in `other`.
+    #[inline]
+    pub fn keys_subset_of<V2>(&self, other: &DirectIdMap<K, V2>) -> bool {
+        self.len <= other.len && self.keys_difference(other).next().is_none()
+    }
+
+    /// The number of slots up to and including the largest key.
+    #[inline]
+    fn used_slots(&self) -> usize {
+        self.values
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |index| index + 1)
+    }
+
+    /// Iterate over every id from zero up to and including [`Self::max_id`],
+    /// along with its value if present.
+    ///
+    /// Unlike [`Self::iter`], this does not skip empty slots,
+    /// so the length is `max_id + 1`, or zero for an empty map.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let map: DirectIdMap<u32, &str> = direct_idmap! { 0 => "foo", 2 => "bar" };
+    /// assert_eq!(
+    ///     map.iter_with_holes().collect::<Vec<_>>(),
+    ///     [(0, Some(&"foo")), (1, None), (2, Some(&"bar"))],
+    /// );
+    /// ```
+    #[inline]
+    pub fn iter_with_holes(&self) -> IterWithHoles<'_, K, V> {
+        IterWithHoles {
+            marker: PhantomData,
+            source: self.values[..self.used_slots()].iter().enumerate(),
+        }
+    }
+
+    /// Mutably iterate over every id from zero up to and including [`Self::max_id`],
+    /// along with its value if present.
+    ///
+    /// See [`Self::iter_with_holes`] for details.
+    #[inline]
+    pub fn iter_with_holes_mut(&mut self) -> IterWithHolesMut<'_, K, V> {
+        let used = self.used_slots();
+        IterWithHolesMut {
+            marker: PhantomData,
+            source: self.values[..used].iter_mut().enumerate(),
+        }
+    }
+
+    /// Iterate over the ids up to [`Self::max_id`] which have no entry.
+    ///
+    /// This is empty for an empty map and for a map without any holes.
+    /// Guaranteed to be sorted by id.
+    #[inline]
+    pub fn missing_ids(&self) -> MissingIds<'_, K, V> {
+        MissingIds {
+            source: self.iter_with_holes(),
+        }
+    }
+
+    /// The number of ids up to [`Self::max_id`] which have no entry.
+    #[inline]
+    pub fn hole_count(&self) -> usize {
+        self.used_slots() - self.len
+    }
+
+    /// Iterate over the entries whose keys are greater than or equal to `key`.
+    ///
+    /// This is useful for resuming iteration after the last key seen,
+    /// and is equivalent to `self.range(key..)`.
+    #[inline]
+    pub fn iter_from(&self, key: K) -> Range<'_, K, V> {
+        self.range(key..)
+    }
+
+    /// Iterate over the entries whose integer ids are greater than or equal to `id`.
+    ///
+    /// Unlike [`Self::iter_from`], this doesn't require a valid key.
+    #[inline]
+    pub fn iter_from_id(&self, id: K::Int) -> Range<'_, K, V> {
+        let start = intid::uint::to_usize_checked(id).unwrap_or(usize::MAX);
+        Range {
+            marker: PhantomData,
+            source: self.values.iter().enumerate().skip(start),
+        }
+    }
+
+    /// Iterate over the entries whose keys are in the specified set.
+    ///
+    /// Only the ids in the set are visited, skipping those without an entry,
+    /// and stopping once they exceed the size of the table.
+    /// Guaranteed to be sorted by the integer id of the key.
+    ///
+    /// The bitset skips a whole word of absent ids at a time,
+    /// and each id in the set jumps directly to its slot.
+    /// Since the table has no separate index of occupied slots,
+    /// walking the map instead would always scan every slot,
+    /// so the set is the cheaper side to walk regardless of the relative sizes.
+    #[inline]
+    pub fn iter_masked<'a>(&'a self, mask: &'a DirectIdSet<K>) -> Masked<'a, K, V> {
+        Masked {
+            marker: PhantomData,
+            mask: mask.bits().ones(),
+            source: self.values.iter(),
+            next_index: 0,
+        }
+    }
+
+    /// Mutably iterate over the entries whose keys are in the specified set.
+    ///
+    /// See [`Self::iter_masked`] for details.
+    #[inline]
+    pub fn iter_masked_mut<'a>(&'a mut self, mask: &'a DirectIdSet<K>) -> MaskedMut<'a, K, V> {
+        MaskedMut {
+            marker: PhantomData,
+            mask: mask.bits().ones(),
+            source: self.values.iter_mut(),
+            next_index: 0,
+        }
+    }
+
+    /// Check if the keys of the map are exactly the ids `0..len`, without any holes.
+    ///
+    /// An empty map is considered dense.
+    #[inline]
+    pub fn is_dense(&self) -> bool {
+        self.hole_count() == 0
+    }
+
+    /// The fraction of ids up to [`Self::max_id`] which have an entry,
+    /// between zero and one.
+    ///
+    /// An empty map is considered dense, with a density of one.
+    #[inline]
+    pub fn density(&self) -> f64 {
+        match self.used_slots() {
+            0 => 1.0,
+            used => self.len as f64 / used as f64,
+        }
+    }
+
+    /// Iterate over the entries whose keys fall within the specified range.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    /// Ranges extending past the largest key are truncated,
+    /// and a range whose start is after its end is treated as empty.
+    #[inline]
+    pub fn range(&self, range: impl RangeBounds<K>) -> Range<'_, K, V> {
+        let slots = self.slot_range(range);
+        Range {
+            marker: PhantomData,
+            source: self.values[..slots.end]
+                .iter()
+                .enumerate()
+                .skip(slots.start),
+        }
+    }
+
+    /// Mutably iterate over the entries whose keys fall within the specified range.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    /// Ranges extending past the largest key are truncated,
+    /// and a range whose start is after its end is treated as empty.
+    #[inline]
+    pub fn range_mut(&mut self, range: impl RangeBounds<K>) -> RangeMut<'_, K, V> {
+        let slots = self.slot_range(range);
+        RangeMut {
+            marker: PhantomData,
+            source: self.values[..slots.end]
+                .iter_mut()
+                .enumerate()
+                .skip(slots.start),
+        }
+    }
+
+    /// Remove the entries whose keys fall within the specified range,
+    /// returning them as an iterator.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    /// If the iterator is dropped before it is exhausted,
+    /// the remaining entries in the range are still removed.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let mut map: DirectIdMap<u32, &str> = direct_idmap! { 1 => "a", 3 => "b", 5 => "c" };
+    /// assert_eq!(map.drain_range(2..=5).collect::<Vec<_>>(), [(3, "b"), (5, "c")]);
+    /// assert_eq!(map, direct_idmap! { 1 => "a" });
+    /// ```
+    pub fn drain_range(&mut self, range: impl RangeBounds<K>) -> DrainRange<'_, K, V> {
+        let slots = self.slot_range(range);
+        let DirectIdMap { values, len, .. } = self;
+        DrainRange {
+            marker: PhantomData,
+            source: values[..slots.end].iter_mut().enumerate().skip(slots.start),
+            len,
+        }
+    }
+
+    /// Convert a range of keys into a range of slot indexes,
+    /// clamped to the length of the table.
+    fn slot_range(&self, range: impl RangeBounds<K>) -> core::ops::Range<usize> {
+        let len = self.values.len();
+        let index = |key: &K| intid::uint::to_usize_checked(key.to_int()).unwrap_or(usize::MAX);
+        let start = match range.start_bound() {
+            Bound::Included(key) => index(key),
+            Bound::Excluded(key) => index(key).saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(key) => index(key).saturating_add(1),
+            Bound::Excluded(key) => index(key),
+            Bound::Unbounded => len,
+        };
+        let end = end.min(len);
+        start.min(end)..end
+    }
+
+    /// Collect the keys of the map into a [`DirectIdSet`].
+    ///
+    /// The set is sized to the table up front,
+    /// so it is built in a single pass without reallocating.
+    pub fn keys_set(&self) -> DirectIdSet<K> {
+        let mut set = DirectIdSet::with_capacity(self.values.len());
+        set.extend(self.keys());
+        set
+    }
+
+    /// Iterate over the integer ids of the keys in the map, along with their values.
+    ///
+    /// Guaranteed to be sorted by id.
+    #[inline]
+    pub fn iter_ids(&self) -> IterIds<'_, K, V> {
+        IterIds {
+            marker: PhantomData,
+            len: self.len,
+            source: self.values.iter().enumerate(),
+        }
+    }
+
+    /// Iterate over the integer ids of the keys in the map.
+    ///
+    /// Guaranteed to be sorted by id.
+    #[inline]
+    pub fn ids(&self) -> Ids<'_, K, V> {
+        Ids {
+            marker: PhantomData,
+            len: self.len,
+            source: self.values.iter().enumerate(),
+        }
+    }
+
+    /// Iterate over the keys in the map.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    #[inline]
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys {
+            marker: PhantomData,
+            len: self.len,
+            source: self.values.iter().enumerate(),
+        }
+    }
+
+    /// Iterate over the values in the map.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    #[inline]
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values {
+            marker: PhantomData,
+            len: self.len,
+            source: self.values.iter().enumerate(),
+        }
+    }
+
+    /// Mutably iterate over the values in the map.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    #[inline]
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            marker: PhantomData,
+            len: self.len,
+            source: self.values.iter_mut().enumerate(),
+        }
+    }
+
+    /// Consume the map, iterating over its keys.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    #[inline]
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys {
+            marker: PhantomData,
+            len: self.len,
+            source: self.values.into_iter().enumerate(),
+        }
+    }
+
+    /// Consume the map, iterating over its values.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    #[inline]
+    pub fn into_values(self) -> IntoValues<K, V> {
+        IntoValues {
+            marker: PhantomData,
+            len: self.len,
+            source: self.values.into_iter().enumerate(),
+        }
+    }
+
+    /// Remove all entries from the map, returning them as an iterator.
+    ///
+    /// The map is cleared even if the iterator is dropped before being fully consumed.
+    /// Like [`Self::clear`], this retains the allocated memory for reuse.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    #[inline]
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        let len = core::mem::take(&mut self.len);
+        Drain {
+            marker: PhantomData,
+            len,
+            source: self.values.drain(..).enumerate(),
+        }
+    }
+
+    /// Create an iterator which removes and yields the entries
+    /// where the specified predicate returns true.
+    ///
+    /// Entries where the predicate returns false are left in the map.
+    /// If the iterator is dropped before being fully consumed,
+    /// the remaining matching entries are still removed.
+    /// However, if the predicate panics no further entries will be removed.
+    ///
+    /// Guaranteed to be sorted by the integer id of the key.
+    /// See also [`Self::retain`].
+    #[inline]
+    pub fn extract_if<F: FnMut(K, &mut V) -> bool>(&mut self, pred: F) -> ExtractIf<'_, K, V, F> {
+        ExtractIf {
+            map: self,
+            index: 0,
+            pred,
+            panicked: false,
+        }
+    }
+
+    /// Transform every value in the map, keeping the same keys.
+    ///
+    /// The layout of the slot table is preserved,
+    /// so no per-key lookups are needed to build the result.
+    pub fn map_values<U, F: FnMut(K, V) -> U>(self, mut func: F) -> DirectIdMap<K, U> {
+        let values = self
+            .values
+            .into_iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                entry.map(|value| {
+                    // SAFETY: If entry exists, the key is guaranteed to be valid
+                    let key =
+                        unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) };
+                    func(key, value)
+                })
+            })
+            .collect();
+        DirectIdMap {
+            values,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    /// Remove every entry whose key is not in the specified set.
+    ///
+    /// The table is walked in chunks alongside the words of the bitset,
+    /// so no keys are constructed and no callbacks are made.
+    /// Slots past the end of the bitset are treated as missing from the set.
+    pub fn retain_in_set(&mut self, set: &DirectIdSet<K>) {
+        let words = set.bits().as_slice();
+        for (word_index, chunk) in self.values.chunks_mut(WORD_BITS).enumerate() {
+            let word: Word = words.get(word_index).copied().unwrap_or(0);
+            if word == Word::MAX {
+                continue; // every slot in the chunk is kept
+            }
+            for (bit, entry) in chunk.iter_mut().enumerate() {
+                if (word >> bit) & 1 == 0 && entry.take().is_some() {
+                    self.len -= 1;
+                }
+            }
+        }
+    }
+
+    /// Remove every entry whose key is in the specified set.
+    ///
+    /// Only the ids in the set are visited,
+    /// stopping once they exceed the size of the table.
+    pub fn remove_in_set(&mut self, set: &DirectIdSet<K>) {
+        for index in set.bits().ones() {
+            let Some(entry) = self.values.get_mut(index) else {
+                break; // ascending, so remaining ids are also out of bounds
+            };
+            if entry.is_some() {
+                self.len -= 1;
+                *entry = None;
+            }
+        }
+    }
+
+    /// Remove every key which is present in the other map,
+    /// returning the number of entries removed.
+    ///
+    /// The two tables are walked together by index,
+    /// stopping at the end of the shorter one.
+    /// Iterating the keys of the other map would scan its whole table anyway,
+    /// so this is never slower than removing them one at a time.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let mut subscribed: DirectIdMap<u32, &str> = direct_idmap! { 1 => "a", 2 => "b", 7 => "c" };
+    /// let unsubscribed: DirectIdMap<u32, ()> = direct_idmap! { 2 => (), 7 => (), 9 => () };
+    /// assert_eq!(subscribed.remove_keys_of(&unsubscribed), 2);
+    /// assert_eq!(subscribed, direct_idmap! { 1 => "a" });
+    /// ```
+    pub fn remove_keys_of<V2>(&mut self, other: &DirectIdMap<K, V2>) -> usize {
+        let mut removed = 0;
+        for (entry, other) in self.values.iter_mut().zip(&other.values) {
+            if entry.is_some() && other.is_some() {
+                *entry = None;
+                removed += 1;
+            }
+        }
+        self.len -= removed;
+        removed
+    }
+
+    /// Convert the map to a different key type,
+    /// moving each value to the key returned by the function.
+    ///
+    /// Values are moved without being cloned.
+    /// Returns an error if two entries are mapped to the same key,
+    /// which holds every entry so that none are lost.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let sparse: DirectIdMap<u32, &str> = direct_idmap! { 100 => "foo", 2000 => "bar" };
+    /// let compact = sparse.map_keys(|id, _| (id / 100) as u8).unwrap();
+    /// assert_eq!(compact, direct_idmap! { 1 => "foo", 20 => "bar" });
+    /// ```
+    pub fn map_keys<K2: IntegerId>(
+        self,
+        mut func: impl FnMut(K, &V) -> K2,
+    ) -> Result<DirectIdMap<K2, V>, DuplicateKeyError<K, K2, V>> {
+        let mut res = DirectIdMap::new();
+        let mut remaining = self;
+        for index in 0..remaining.values.len() {
+            let Some(value) = remaining.values[index].take() else {
+                continue;
+            };
+            remaining.len -= 1;
+            // SAFETY: Value exists => index is valid
+            let key = unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) };
+            let new_key = func(key, &value);
+            if let Err(error) = res.try_insert(new_key, value) {
+                let value = error.value;
+                return Err(DuplicateKeyError {
+                    key: new_key,
+                    source_key: key,
+                    value,
+                    mapped: res,
+                    remaining,
+                });
+            }
+        }
+        Ok(res)
+    }
+
+    /// Transform every value in a single pass,
+    /// removing entries where the function returns `None`.
+    ///
+    /// The function takes ownership of each value,
+    /// and returning `Some` stores the new value in its place.
+    /// If the function panics, the value it was given is dropped
+    /// and its entry is removed.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let mut map: DirectIdMap<u32, u32> = direct_idmap! { 1 => 10, 2 => 15, 4 => 20 };
+    /// map.retain_filter_map(|_, value| (value % 10 == 0).then(|| value / 10));
+    /// assert_eq!(map, direct_idmap! { 1 => 1, 4 => 2 });
+    /// ```
+    pub fn retain_filter_map(&mut self, mut func: impl FnMut(K, V) -> Option<V>) {
+        for (index, entry) in self.values.iter_mut().enumerate() {
+            let Some(value) = entry.take() else { continue };
+            // SAFETY: If entry exists, the key is guaranteed to be valid
+            let key = unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) };
+            // the slot is empty while the function runs, in case it panics
+            self.len -= 1;
+            if let Some(value) = func(key, value) {
+                *entry = Some(value);
+                self.len += 1;
+            }
+        }
+    }
+
+    /// Split the map in two according to a predicate,
+    /// returning the entries where it returns `true` followed by the rest.
+    ///
+    /// The first map reuses the allocation of the original.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::{direct_idmap, DirectIdMap};
+    /// let map: DirectIdMap<u32, u32> = direct_idmap! { 1 => 10, 2 => 15, 4 => 20 };
+    /// let (even, odd) = map.partition(|_, value| value % 2 == 0);
+    /// assert_eq!(even, direct_idmap! { 1 => 10, 4 => 20 });
+    /// assert_eq!(odd, direct_idmap! { 2 => 15 });
+    /// ```
+    pub fn partition(mut self, mut pred: impl FnMut(K, &V) -> bool) -> (Self, Self) {
+        let mut rejected = Self::new();
+        for (index, entry) in self.values.iter_mut().enumerate() {
+            let Some(value) = entry else { continue };
+            // SAFETY: If entry exists, the key is guaranteed to be valid
+            let key = unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) };
+            if !pred(key, value) {
+                self.len -= 1;
+                rejected.insert(key, entry.take().unwrap());
+            }
+        }
+        self.trim_trailing_holes();
+        (self, rejected)
+    }
+
+    /// Iterate over the entries in the map,
+    /// removing entries when the callback returns false.
+    ///
+    /// See also [std::collections::HashMap::retain].
+    pub fn retain(&mut self, mut func: impl FnMut(K, &mut V) -> bool) {
+        for (index, entry) in self.values.iter_mut().enumerate() {
+            if entry.is_none() {
+                continue;
+            }
+            // SAFETY: If entry exists, the key is guaranteed to be valid
+            let key = unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) };
+            if !func(key, entry.as_mut().unwrap()) {
+                *entry = None;
+                self.len -= 1;
+            }
+        }
+    }
+}
+impl<K: IntegerId, V: PartialEq> PartialEq for DirectIdMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        // compare entries rather than slots, ignoring trailing empty slots
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+impl<K: IntegerId, V: Eq> Eq for DirectIdMap<K, V> {}
+impl<K, V, S> PartialEq<HashMap<K, V, S>> for DirectIdMap<K, V>
+where
+    K: IntegerId + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &HashMap<K, V, S>) -> bool {
+        self.len == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(&key) == Some(value))
+    }
+}
+impl<K, V, S> PartialEq<DirectIdMap<K, V>> for HashMap<K, V, S>
+where
+    K: IntegerId + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    #[inline]
+    fn eq(&self, other: &DirectIdMap<K, V>) -> bool {
+        other == self
+    }
+}
+impl<K: IntegerId + Ord, V: PartialEq> PartialEq<BTreeMap<K, V>> for DirectIdMap<K, V> {
+    fn eq(&self, other: &BTreeMap<K, V>) -> bool {
+        // The ordering of `K` may disagree with the integer ids, so probe instead of zipping
+        self.len == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(&key) == Some(value))
+    }
+}
+impl<K: IntegerId + Ord, V: PartialEq> PartialEq<DirectIdMap<K, V>> for BTreeMap<K, V> {
+    #[inline]
+    fn eq(&self, other: &DirectIdMap<K, V>) -> bool {
+        other == self
+    }
+}
+impl<K: IntegerId + Hash, V: Hash> Hash for DirectIdMap<K, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        // guaranteed to be ordered by key, and independent of empty slots
+        for (key, value) in self.iter() {
+            key.hash(state);
+            value.hash(state);
+        }
+    }
+}
+impl<K: IntegerId, V> Index<K> for DirectIdMap<K, V> {
+    type Output = V;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, index: K) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+impl<K: IntegerId, V> IndexMut<K> for DirectIdMap<K, V> {
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, index: K) -> &mut Self::Output {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+impl<'a, K: IntegerId, V> Index<&'a K> for DirectIdMap<K, V> {
+    type Output = V;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, index: &'a K) -> &Self::Output {
+        self.get(*index).expect("index out of bounds")
+    }
+}
+impl<'a, K: IntegerId, V> IndexMut<&'a K> for DirectIdMap<K, V> {
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, index: &'a K) -> &mut Self::Output {
+        self.get_mut(*index).expect("index out of bounds")
+    }
+}
+impl<K: IntegerId, V> Extend<(K, V)> for DirectIdMap<K, V> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+impl<'a, K: IntegerId, V: Clone> Extend<(K, &'a V)> for DirectIdMap<K, V> {
+    fn extend<T: IntoIterator<Item = (K, &'a V)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value.clone());
+        }
+    }
+}
+
+impl<K: IntegerId, V> FromIterator<(K, V)> for DirectIdMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut res = Self::new();
+        res.extend(iter);
+        res
+    }
+}
+impl<'a, K: IntegerId, V: Clone> FromIterator<(K, &'a V)> for DirectIdMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, &'a V)>>(iter: I) -> Self {
+        let mut res = Self::new();
+        res.extend(iter);
+        res
+    }
+}
+/// Create a map from an array of key-value pairs.
+///
+/// This behaves like [`FromIterator`],
+/// so later values overwrite earlier ones with the same key.
+///
+/// ## Example
+/// ```
+/// # use idmap::DirectIdMap;
+/// let map = DirectIdMap::<u32, _>::from([(1, "a"), (25, "b"), (1, "c")]);
+/// assert_eq!(map[1], "c");
+/// assert_eq!(map.len(), 2);
+/// ```
+impl<K: IntegerId, V, const N: usize> From<[(K, V); N]> for DirectIdMap<K, V> {
+    #[inline]
+    fn from(entries: [(K, V); N]) -> Self {
+        entries.into_iter().collect()
     }
 }
-impl<'a, K: IntegerId, V> IndexMut<&'a K> for DirectIdMap<K, V> {
+/// Create a map from a vector of key-value pairs.
+///
+/// This behaves like [`FromIterator`],
+/// so later values overwrite earlier ones with the same key.
+impl<K: IntegerId, V> From<Vec<(K, V)>> for DirectIdMap<K, V> {
     #[inline]
-    #[track_caller]
-    fn index_mut(&mut self, index: &'a K) -> &mut Self::Output {
-        self.get_mut(*index).expect("index out of bounds")
+    fn from(entries: Vec<(K, V)>) -> Self {
+        entries.into_iter().collect()
     }
 }
-impl<K: IntegerId, V> Extend<(K, V)> for DirectIdMap<K, V> {
-    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
-        for (key, value) in iter {
-            self.insert(key, value);
+impl<K: IntegerId, V, S> From<HashMap<K, V, S>> for DirectIdMap<K, V> {
+    fn from(map: HashMap<K, V, S>) -> Self {
+        let max_id = map.keys().map(|key| key.to_int()).max();
+        Self::from_unsorted(map, max_id)
+    }
+}
+impl<K: IntegerId, V> From<BTreeMap<K, V>> for DirectIdMap<K, V> {
+    fn from(map: BTreeMap<K, V>) -> Self {
+        // The ordering of `K` may disagree with the integer ids,
+        // so the last key is not necessarily the largest.
+        let max_id = map.keys().map(|key| key.to_int()).max();
+        Self::from_unsorted(map, max_id)
+    }
+}
+impl<K: IntegerId + Hash, V, S: BuildHasher + Default> From<DirectIdMap<K, V>>
+    for HashMap<K, V, S>
+{
+    #[inline]
+    fn from(map: DirectIdMap<K, V>) -> Self {
+        map.into_iter().collect()
+    }
+}
+impl<K: IntegerId + Ord, V> From<DirectIdMap<K, V>> for BTreeMap<K, V> {
+    #[inline]
+    fn from(map: DirectIdMap<K, V>) -> Self {
+        // entries are yielded by ascending id, which is usually already sorted
+        map.into_iter().collect()
+    }
+}
+impl<K: IntegerId, V> IntoIterator for DirectIdMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            len: self.len,
+            source: self.values.into_iter().enumerate(),
+            marker: PhantomData,
         }
     }
 }
-impl<'a, K: IntegerId, V: Clone> Extend<(K, &'a V)> for DirectIdMap<K, V> {
-    fn extend<T: IntoIterator<Item = (K, &'a V)>>(&mut self, iter: T) {
-        for (key, value) in iter {
-            self.insert(key, value.clone());
+impl<'a, K: IntegerId, V> IntoIterator for &'a DirectIdMap<K, V> {
+    type Item = (K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: IntegerId, V> IntoIterator for &'a mut DirectIdMap<K, V> {
+    type Item = (K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+impl<K: IntegerId, V: Debug> Debug for DirectIdMap<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Debugs a [`DirectIdMap`] using the integer ids of the keys.
+///
+/// Returned by [`DirectIdMap::debug_ids`].
+pub struct DebugIds<'a, K: IntegerId, V> {
+    map: &'a DirectIdMap<K, V>,
+}
+impl<K: IntegerId, V: Debug> Debug for DebugIds<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_map()
+            .entries(self.map.iter().map(|(key, value)| (key.to_int(), value)))
+            .finish()
+    }
+}
+
+/// Counts the changes made by [`DirectIdMap::apply_patch`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PatchSummary {
+    /// The number of keys which were missing and have been inserted.
+    pub inserted: usize,
+    /// The number of keys whose existing value was replaced.
+    pub updated: usize,
+    /// The number of keys which were present and have been removed.
+    pub removed: usize,
+}
+macro_rules! impl_direct_iter {
+    ($target:ident<$($l:lifetime,)? $kt:ident, $vt:ident> {
+        fn map($k:ident, $v:ident) -> $item_ty:ty {
+            $map:expr
+        }
+    }) => {
+        impl<$($l,)* $kt: IntegerId, $vt> Iterator for $target<$($l,)* $kt, $vt> {
+            type Item = $item_ty;
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                loop {
+                    match self.source.next() {
+                        Some((index, Some($v))) => {
+                            // SAFETY: Value exists => index is valid
+                            let $k = unsafe {
+                                $kt::from_int_unchecked(intid::uint::from_usize_wrapping(index))
+                            };
+                            self.len -= 1;
+                            return Some($map)
+                        },
+                        Some((_, None)) => continue,
+                        None => return None,
+                    }
+                }
+            }
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (self.len, Some(self.len))
+            }
+            #[inline]
+            fn fold<B, F: FnMut(B, Self::Item) -> B>(self, init: B, mut func: F) -> B {
+                // Internal iteration over the slots avoids the per-item loop in `next`
+                self.source.fold(init, |acc, (index, entry)| match entry {
+                    Some($v) => {
+                        // SAFETY: Value exists => index is valid
+                        let $k = unsafe {
+                            $kt::from_int_unchecked(intid::uint::from_usize_wrapping(index))
+                        };
+                        func(acc, $map)
+                    }
+                    None => acc,
+                })
+            }
+        }
+        impl<$($l,)* $kt: IntegerId, $vt> DoubleEndedIterator for $target<$($l,)* $kt, $vt> {
+            #[inline]
+            fn next_back(&mut self) -> Option<Self::Item> {
+                loop {
+                    match self.source.next_back() {
+                        Some((index, Some($v))) => {
+                            // SAFETY: Value exists => index is valid
+                            let $k = unsafe {
+                                $kt::from_int_unchecked(intid::uint::from_usize_wrapping(index))
+                            };
+                            self.len -= 1;
+                            return Some($map)
+                        },
+                        Some((_, None)) => continue,
+                        None => return None,
+                    }
+                }
+            }
+        }
+        impl<$($l,)* $kt: IntegerId, $vt> ExactSizeIterator for $target<$($l,)* $kt, $vt> {}
+        impl<$($l,)* $kt: IntegerId, $vt> Debug for $target<$($l,)* $kt, $vt> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct(stringify!($target))
+                    .field("len", &self.len)
+                    .finish_non_exhaustive()
+            }
+        }
+        impl<$($l,)* $kt: IntegerId, $vt> core::iter::FusedIterator for $target<$($l,)* $kt, $vt> {}
+    }
+}
+/// An iterator consuming the entries in a [`DirectIdMap`]/
+///
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct IntoIter<K: IntegerId, V> {
+    source: core::iter::Enumerate<alloc::vec::IntoIter<Option<V>>>,
+    len: usize,
+    marker: PhantomData<K>,
+}
+impl_direct_iter!(IntoIter<K, V> {
+    fn map(key, value) -> (K, V) {
+        (key, value)
+    }
+});
+/// An iterator over the entries in a [`DirectIdMap`].
+///
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct Iter<'a, K: IntegerId, V> {
+    source: core::iter::Enumerate<core::slice::Iter<'a, Option<V>>>,
+    len: usize,
+    marker: PhantomData<K>,
+}
+impl_direct_iter!(Iter<'a, K, V> {
+    fn map(key, value) -> (K, &'a V) {
+        (key, value)
+    }
+});
+
+/// A mutable iterator over the entries in a [`DirectIdMap`].
+///
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct IterMut<'a, K: IntegerId, V> {
+    source: core::iter::Enumerate<core::slice::IterMut<'a, Option<V>>>,
+    len: usize,
+    marker: PhantomData<K>,
+}
+impl_direct_iter!(IterMut<'a, K, V> {
+    fn map(key, value) -> (K, &'a mut V) {
+        (key, value)
+    }
+});
+
+/// A draining iterator over the entries in a [`DirectIdMap`].
+///
+/// Returned by [`DirectIdMap::drain`].
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct Drain<'a, K: IntegerId, V> {
+    source: core::iter::Enumerate<alloc::vec::Drain<'a, Option<V>>>,
+    len: usize,
+    marker: PhantomData<K>,
+}
+impl_direct_iter!(Drain<'a, K, V> {
+    fn map(key, value) -> (K, V) {
+        (key, value)
+    }
+});
+
+/// An iterator which removes the entries of a [`DirectIdMap`] matching a predicate.
+///
+/// Returned by [`DirectIdMap::extract_if`].
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct ExtractIf<'a, K: IntegerId, V, F: FnMut(K, &mut V) -> bool> {
+    map: &'a mut DirectIdMap<K, V>,
+    index: usize,
+    pred: F,
+    /// Set while the predicate is running,
+    /// so we can avoid calling it again when dropped after a panic.
+    panicked: bool,
+}
+impl<K: IntegerId, V, F: FnMut(K, &mut V) -> bool> Iterator for ExtractIf<'_, K, V, F> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(entry) = self.map.values.get_mut(self.index) {
+            let index = self.index;
+            self.index += 1;
+            let Some(value) = entry.as_mut() else {
+                continue;
+            };
+            // SAFETY: If entry exists, the key is guaranteed to be valid
+            let key = unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) };
+            self.panicked = true;
+            let matches = (self.pred)(key, value);
+            self.panicked = false;
+            if matches {
+                self.map.len -= 1;
+                return entry.take().map(|value| (key, value));
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.map.len))
+    }
+}
+impl<K: IntegerId, V, F: FnMut(K, &mut V) -> bool> core::iter::FusedIterator
+    for ExtractIf<'_, K, V, F>
+{
+}
+impl<K: IntegerId, V, F: FnMut(K, &mut V) -> bool> Drop for ExtractIf<'_, K, V, F> {
+    fn drop(&mut self) {
+        if !self.panicked {
+            self.for_each(drop);
+        }
+    }
+}
+
+/// An iterator removing a range of entries from a [`DirectIdMap`].
+///
+/// Returned by [`DirectIdMap::drain_range`].
+/// Guaranteed to be ordered by the integer value of the key.
+/// Any remaining entries in the range are removed when dropped.
+pub struct DrainRange<'a, K: IntegerId, V> {
+    source: core::iter::Skip<core::iter::Enumerate<core::slice::IterMut<'a, Option<V>>>>,
+    len: &'a mut usize,
+    marker: PhantomData<K>,
+}
+impl<K: IntegerId, V> DrainRange<'_, K, V> {
+    #[inline]
+    fn take(&mut self, index: usize, entry: &mut Option<V>) -> Option<(K, V)> {
+        let value = entry.take()?;
+        *self.len -= 1;
+        // SAFETY: Value exists => index is valid
+        let key = unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) };
+        Some((key, value))
+    }
+}
+impl<K: IntegerId, V> Iterator for DrainRange<'_, K, V> {
+    type Item = (K, V);
+    #[inline]
+    fn next(&mut self) -> Option<(K, V)> {
+        loop {
+            let (index, entry) = self.source.next()?;
+            if let Some(entry) = self.take(index, entry) {
+                return Some(entry);
+            }
+        }
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.source.len()))
+    }
+}
+impl<K: IntegerId, V> DoubleEndedIterator for DrainRange<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(K, V)> {
+        loop {
+            let (index, entry) = self.source.next_back()?;
+            if let Some(entry) = self.take(index, entry) {
+                return Some(entry);
+            }
         }
     }
-}
+}
+impl<K: IntegerId, V> core::iter::FusedIterator for DrainRange<'_, K, V> {}
+impl<K: IntegerId, V> Drop for DrainRange<'_, K, V> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
+
+/// A iterator over the values in a [`DirectIdMap`].
+///
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct Values<'a, K: IntegerId, V> {
+    source: core::iter::Enumerate<core::slice::Iter<'a, Option<V>>>,
+    len: usize,
+    marker: PhantomData<K>,
+}
+impl_direct_iter!(Values<'a, K, V> {
+    fn map(_key, value) -> &'a V {
+        value
+    }
+});
+
+/// A mutable iterator over the values in a [`DirectIdMap`].
+///
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct ValuesMut<'a, K: IntegerId, V> {
+    source: core::iter::Enumerate<core::slice::IterMut<'a, Option<V>>>,
+    len: usize,
+    marker: PhantomData<K>,
+}
+impl_direct_iter!(ValuesMut<'a, K, V> {
+    fn map(_key, value) -> &'a mut V {
+        value
+    }
+});
+
+/// A iterator over the keys in a [`DirectIdMap`].
+///
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct Keys<'a, K: IntegerId, V> {
+    source: core::iter::Enumerate<core::slice::Iter<'a, Option<V>>>,
+    len: usize,
+    marker: PhantomData<K>,
+}
+impl_direct_iter!(Keys<'a, K, V> {
+    fn map(key, _value) -> K {
+        key
+    }
+});
 
-impl<K: IntegerId, V> FromIterator<(K, V)> for DirectIdMap<K, V> {
-    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
-        let mut res = Self::new();
-        res.extend(iter);
-        res
-    }
+/// An iterator over the integer ids of the keys in a [`DirectIdMap`],
+/// along with their values.
+///
+/// Returned by [`DirectIdMap::iter_ids`].
+/// Guaranteed to be ordered by id.
+pub struct IterIds<'a, K: IntegerId, V> {
+    source: core::iter::Enumerate<core::slice::Iter<'a, Option<V>>>,
+    len: usize,
+    marker: PhantomData<K>,
 }
-impl<'a, K: IntegerId, V: Clone> FromIterator<(K, &'a V)> for DirectIdMap<K, V> {
-    fn from_iter<I: IntoIterator<Item = (K, &'a V)>>(iter: I) -> Self {
-        let mut res = Self::new();
-        res.extend(iter);
-        res
+impl_direct_iter!(IterIds<'a, K, V> {
+    fn map(key, value) -> (K::Int, &'a V) {
+        (key.to_int(), value)
     }
-}
-impl<K: IntegerId, V> IntoIterator for DirectIdMap<K, V> {
-    type Item = (K, V);
-    type IntoIter = IntoIter<K, V>;
+});
 
-    #[inline]
-    fn into_iter(self) -> Self::IntoIter {
-        IntoIter {
-            len: self.len,
-            source: self.values.into_iter().enumerate(),
-            marker: PhantomData,
-        }
-    }
+/// An iterator over the integer ids of the keys in a [`DirectIdMap`].
+///
+/// Returned by [`DirectIdMap::ids`].
+/// Guaranteed to be ordered by id.
+pub struct Ids<'a, K: IntegerId, V> {
+    source: core::iter::Enumerate<core::slice::Iter<'a, Option<V>>>,
+    len: usize,
+    marker: PhantomData<K>,
 }
-impl<'a, K: IntegerId, V> IntoIterator for &'a DirectIdMap<K, V> {
-    type Item = (K, &'a V);
-    type IntoIter = Iter<'a, K, V>;
-
-    #[inline]
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
+impl_direct_iter!(Ids<'a, K, V> {
+    fn map(key, _value) -> K::Int {
+        key.to_int()
     }
-}
-
-impl<'a, K: IntegerId, V> IntoIterator for &'a mut DirectIdMap<K, V> {
-    type Item = (K, &'a mut V);
-    type IntoIter = IterMut<'a, K, V>;
+});
 
-    #[inline]
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter_mut()
-    }
+/// An iterator consuming the keys in a [`DirectIdMap`].
+///
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct IntoKeys<K: IntegerId, V> {
+    source: core::iter::Enumerate<alloc::vec::IntoIter<Option<V>>>,
+    len: usize,
+    marker: PhantomData<K>,
 }
-impl<K: IntegerId, V: Debug> Debug for DirectIdMap<K, V> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        f.debug_map().entries(self.iter()).finish()
+impl_direct_iter!(IntoKeys<K, V> {
+    fn map(key, _value) -> K {
+        key
     }
+});
+
+/// An iterator consuming the values in a [`DirectIdMap`].
+///
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct IntoValues<K: IntegerId, V> {
+    source: core::iter::Enumerate<alloc::vec::IntoIter<Option<V>>>,
+    len: usize,
+    marker: PhantomData<K>,
 }
-macro_rules! impl_direct_iter {
-    ($target:ident<$($l:lifetime,)? $kt:ident, $vt:ident> {
+impl_direct_iter!(IntoValues<K, V> {
+    fn map(_key, value) -> V {
+        value
+    }
+});
+
+macro_rules! impl_range_iter {
+    ($target:ident<$l:lifetime, $kt:ident, $vt:ident> {
         fn map($k:ident, $v:ident) -> $item_ty:ty {
             $map:expr
         }
     }) => {
-        impl<$($l,)* $kt: IntegerId, $vt> Iterator for $target<$($l,)* $kt, $vt> {
+        impl<$l, $kt: IntegerId, $vt> Iterator for $target<$l, $kt, $vt> {
             type Item = $item_ty;
             #[inline]
             fn next(&mut self) -> Option<Self::Item> {
@@ -291,9 +2412,8 @@ macro_rules! impl_direct_iter {
                             let $k = unsafe {
                                 $kt::from_int_unchecked(intid::uint::from_usize_wrapping(index))
                             };
-                            self.len -= 1;
-                            return Some($map)
-                        },
+                            return Some($map);
+                        }
                         Some((_, None)) => continue,
                         None => return None,
                     }
@@ -301,10 +2421,10 @@ macro_rules! impl_direct_iter {
             }
             #[inline]
             fn size_hint(&self) -> (usize, Option<usize>) {
-                (self.len, Some(self.len))
+                (0, Some(self.source.len()))
             }
         }
-        impl<$($l,)* $kt: IntegerId, $vt> DoubleEndedIterator for $target<$($l,)* $kt, $vt> {
+        impl<$l, $kt: IntegerId, $vt> DoubleEndedIterator for $target<$l, $kt, $vt> {
             #[inline]
             fn next_back(&mut self) -> Option<Self::Item> {
                 loop {
@@ -314,101 +2434,365 @@ macro_rules! impl_direct_iter {
                             let $k = unsafe {
                                 $kt::from_int_unchecked(intid::uint::from_usize_wrapping(index))
                             };
-                            return Some($map)
-                        },
+                            return Some($map);
+                        }
                         Some((_, None)) => continue,
                         None => return None,
                     }
                 }
             }
         }
-        impl<$($l,)* $kt: IntegerId, $vt> ExactSizeIterator for $target<$($l,)* $kt, $vt> {}
-        impl<$($l,)* $kt: IntegerId, $vt> core::iter::FusedIterator for $target<$($l,)* $kt, $vt> {}
-    }
+        impl<$l, $kt: IntegerId, $vt> core::iter::FusedIterator for $target<$l, $kt, $vt> {}
+    };
 }
-/// An iterator consuming the entries in a [`DirectIdMap`]/
+
+/// An iterator over a range of entries in a [`DirectIdMap`].
 ///
+/// Returned by [`DirectIdMap::range`].
 /// Guaranteed to be ordered by the integer value of the key.
-pub struct IntoIter<K: IntegerId, V> {
-    source: core::iter::Enumerate<alloc::vec::IntoIter<Option<V>>>,
-    len: usize,
+pub struct Range<'a, K: IntegerId, V> {
+    source: core::iter::Skip<core::iter::Enumerate<core::slice::Iter<'a, Option<V>>>>,
     marker: PhantomData<K>,
 }
-impl_direct_iter!(IntoIter<K, V> {
-    fn map(key, value) -> (K, V) {
+impl_range_iter!(Range<'a, K, V> {
+    fn map(key, value) -> (K, &'a V) {
         (key, value)
     }
 });
-/// An iterator over the entries in a [`DirectIdMap`].
+
+/// A mutable iterator over a range of entries in a [`DirectIdMap`].
 ///
+/// Returned by [`DirectIdMap::range_mut`].
 /// Guaranteed to be ordered by the integer value of the key.
-pub struct Iter<'a, K: IntegerId, V> {
-    source: core::iter::Enumerate<core::slice::Iter<'a, Option<V>>>,
-    len: usize,
+pub struct RangeMut<'a, K: IntegerId, V> {
+    source: core::iter::Skip<core::iter::Enumerate<core::slice::IterMut<'a, Option<V>>>>,
     marker: PhantomData<K>,
 }
-impl_direct_iter!(Iter<'a, K, V> {
-    fn map(key, value) -> (K, &'a V) {
+impl_range_iter!(RangeMut<'a, K, V> {
+    fn map(key, value) -> (K, &'a mut V) {
         (key, value)
     }
 });
 
-/// A mutable iterator over the entries in a [`DirectIdMap`].
+macro_rules! impl_intersection_iter {
+    ($target:ident<$l:lifetime, $kt:ident, $vt:ident, $ot:ident> -> $item_ty:ty) => {
+        impl<$l, $kt: IntegerId, $vt, $ot> Iterator for $target<$l, $kt, $vt, $ot> {
+            type Item = $item_ty;
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                loop {
+                    match self.source.next() {
+                        Some((index, (Some(value), Some(other)))) => {
+                            // SAFETY: Value exists => index is valid
+                            let key = unsafe {
+                                $kt::from_int_unchecked(intid::uint::from_usize_wrapping(index))
+                            };
+                            return Some((key, value, other));
+                        }
+                        Some(_) => continue,
+                        None => return None,
+                    }
+                }
+            }
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (0, Some(self.source.len()))
+            }
+        }
+        impl<$l, $kt: IntegerId, $vt, $ot> DoubleEndedIterator for $target<$l, $kt, $vt, $ot> {
+            #[inline]
+            fn next_back(&mut self) -> Option<Self::Item> {
+                loop {
+                    match self.source.next_back() {
+                        Some((index, (Some(value), Some(other)))) => {
+                            // SAFETY: Value exists => index is valid
+                            let key = unsafe {
+                                $kt::from_int_unchecked(intid::uint::from_usize_wrapping(index))
+                            };
+                            return Some((key, value, other));
+                        }
+                        Some(_) => continue,
+                        None => return None,
+                    }
+                }
+            }
+        }
+        impl<$l, $kt: IntegerId, $vt, $ot> core::iter::FusedIterator
+            for $target<$l, $kt, $vt, $ot>
+        {
+        }
+    };
+}
+
+/// Walks the slots of two tables together, along with their index.
+type Lockstep<A, B> = core::iter::Enumerate<core::iter::Zip<A, B>>;
+
+/// An iterator over the entries present in two [`DirectIdMap`]s.
 ///
+/// Returned by [`DirectIdMap::iter_intersection`].
 /// Guaranteed to be ordered by the integer value of the key.
-pub struct IterMut<'a, K: IntegerId, V> {
-    source: core::iter::Enumerate<core::slice::IterMut<'a, Option<V>>>,
-    len: usize,
+pub struct Intersection<'a, K: IntegerId, V, V2> {
+    source: Lockstep<core::slice::Iter<'a, Option<V>>, core::slice::Iter<'a, Option<V2>>>,
     marker: PhantomData<K>,
 }
-impl_direct_iter!(IterMut<'a, K, V> {
-    fn map(key, value) -> (K, &'a mut V) {
-        (key, value)
-    }
-});
+impl_intersection_iter!(Intersection<'a, K, V, V2> -> (K, &'a V, &'a V2));
 
-/// A iterator over the values in a [`DirectIdMap`].
+/// An iterator over the entries present in two [`DirectIdMap`]s,
+/// giving mutable access to the values of the first.
 ///
+/// Returned by [`DirectIdMap::iter_intersection_mut`].
 /// Guaranteed to be ordered by the integer value of the key.
-pub struct Values<'a, K: IntegerId, V> {
+pub struct IntersectionMut<'a, K: IntegerId, V, V2> {
+    source: Lockstep<core::slice::IterMut<'a, Option<V>>, core::slice::Iter<'a, Option<V2>>>,
+    marker: PhantomData<K>,
+}
+impl_intersection_iter!(IntersectionMut<'a, K, V, V2> -> (K, &'a mut V, &'a V2));
+
+/// An iterator over the keys present in one [`DirectIdMap`] but not another.
+///
+/// Returned by [`DirectIdMap::keys_difference`].
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct KeysDifference<'a, K: IntegerId, V, V2> {
     source: core::iter::Enumerate<core::slice::Iter<'a, Option<V>>>,
-    len: usize,
+    other: &'a [Option<V2>],
     marker: PhantomData<K>,
 }
-impl_direct_iter!(Values<'a, K, V> {
-    fn map(_key, value) -> &'a V {
-        value
+impl<K: IntegerId, V, V2> KeysDifference<'_, K, V, V2> {
+    #[inline]
+    fn key_at(&self, index: usize, slot: &Option<V>) -> Option<K> {
+        if slot.is_some() && !matches!(self.other.get(index), Some(Some(_))) {
+            // SAFETY: Value exists => index is valid
+            Some(unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) })
+        } else {
+            None
+        }
     }
-});
+}
+impl<K: IntegerId, V, V2> Iterator for KeysDifference<'_, K, V, V2> {
+    type Item = K;
+    #[inline]
+    fn next(&mut self) -> Option<K> {
+        loop {
+            let (index, slot) = self.source.next()?;
+            if let Some(key) = self.key_at(index, slot) {
+                return Some(key);
+            }
+        }
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.source.len()))
+    }
+}
+impl<K: IntegerId, V, V2> DoubleEndedIterator for KeysDifference<'_, K, V, V2> {
+    #[inline]
+    fn next_back(&mut self) -> Option<K> {
+        loop {
+            let (index, slot) = self.source.next_back()?;
+            if let Some(key) = self.key_at(index, slot) {
+                return Some(key);
+            }
+        }
+    }
+}
+impl<K: IntegerId, V, V2> core::iter::FusedIterator for KeysDifference<'_, K, V, V2> {}
 
-/// A mutable iterator over the values in a [`DirectIdMap`].
+/// An iterator over the keys present in exactly one of two [`DirectIdMap`]s.
 ///
+/// Returned by [`DirectIdMap::keys_symmetric_difference`].
 /// Guaranteed to be ordered by the integer value of the key.
-pub struct ValuesMut<'a, K: IntegerId, V> {
-    source: core::iter::Enumerate<core::slice::IterMut<'a, Option<V>>>,
-    len: usize,
+pub struct KeysSymmetricDifference<'a, K: IntegerId, V, V2> {
+    indexes: core::ops::Range<usize>,
+    left: &'a [Option<V>],
+    right: &'a [Option<V2>],
     marker: PhantomData<K>,
 }
-impl_direct_iter!(ValuesMut<'a, K, V> {
-    fn map(_key, value) -> &'a mut V {
-        value
+impl<K: IntegerId, V, V2> KeysSymmetricDifference<'_, K, V, V2> {
+    #[inline]
+    fn key_at(&self, index: usize) -> Option<K> {
+        let in_left = matches!(self.left.get(index), Some(Some(_)));
+        let in_right = matches!(self.right.get(index), Some(Some(_)));
+        if in_left != in_right {
+            // SAFETY: Value exists => index is valid
+            Some(unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(index)) })
+        } else {
+            None
+        }
+    }
+}
+impl<K: IntegerId, V, V2> Iterator for KeysSymmetricDifference<'_, K, V, V2> {
+    type Item = K;
+    #[inline]
+    fn next(&mut self) -> Option<K> {
+        loop {
+            let index = self.indexes.next()?;
+            if let Some(key) = self.key_at(index) {
+                return Some(key);
+            }
+        }
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.indexes.len()))
+    }
+}
+impl<K: IntegerId, V, V2> DoubleEndedIterator for KeysSymmetricDifference<'_, K, V, V2> {
+    #[inline]
+    fn next_back(&mut self) -> Option<K> {
+        loop {
+            let index = self.indexes.next_back()?;
+            if let Some(key) = self.key_at(index) {
+                return Some(key);
+            }
+        }
     }
+}
+impl<K: IntegerId, V, V2> core::iter::FusedIterator for KeysSymmetricDifference<'_, K, V, V2> {}
+
+macro_rules! impl_holes_iter {
+    ($target:ident<$l:lifetime, $kt:ident, $vt:ident> -> Option<$value_ty:ty> {
+        fn map($slot:ident) { $map:expr }
+    }) => {
+        impl<$l, $kt: IntegerId, $vt> Iterator for $target<$l, $kt, $vt> {
+            type Item = ($kt::Int, Option<$value_ty>);
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                let (index, $slot) = self.source.next()?;
+                Some((intid::uint::from_usize_wrapping(index), $map))
+            }
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.source.size_hint()
+            }
+        }
+        impl<$l, $kt: IntegerId, $vt> DoubleEndedIterator for $target<$l, $kt, $vt> {
+            #[inline]
+            fn next_back(&mut self) -> Option<Self::Item> {
+                let (index, $slot) = self.source.next_back()?;
+                Some((intid::uint::from_usize_wrapping(index), $map))
+            }
+        }
+        impl<$l, $kt: IntegerId, $vt> ExactSizeIterator for $target<$l, $kt, $vt> {}
+        impl<$l, $kt: IntegerId, $vt> core::iter::FusedIterator for $target<$l, $kt, $vt> {}
+    };
+}
+
+/// An iterator over every id up to the largest key in a [`DirectIdMap`],
+/// including ids without an entry.
+///
+/// Returned by [`DirectIdMap::iter_with_holes`].
+/// Guaranteed to be ordered by id.
+pub struct IterWithHoles<'a, K: IntegerId, V> {
+    source: core::iter::Enumerate<core::slice::Iter<'a, Option<V>>>,
+    marker: PhantomData<K>,
+}
+impl_holes_iter!(IterWithHoles<'a, K, V> -> Option<&'a V> {
+    fn map(slot) { slot.as_ref() }
 });
 
-/// A iterator over the keys in a [`DirectIdMap`].
+/// A mutable iterator over every id up to the largest key in a [`DirectIdMap`],
+/// including ids without an entry.
 ///
-/// Guaranteed to be ordered by the integer value of the key.
-pub struct Keys<'a, K: IntegerId, V> {
+/// Returned by [`DirectIdMap::iter_with_holes_mut`].
+/// Guaranteed to be ordered by id.
+pub struct IterWithHolesMut<'a, K: IntegerId, V> {
     source: core::iter::Enumerate<core::slice::IterMut<'a, Option<V>>>,
-    len: usize,
     marker: PhantomData<K>,
 }
-impl_direct_iter!(Keys<'a, K, V> {
-    fn map(key, _value) -> K {
-        key
-    }
+impl_holes_iter!(IterWithHolesMut<'a, K, V> -> Option<&'a mut V> {
+    fn map(slot) { slot.as_mut() }
 });
 
+/// An iterator over the missing ids up to the largest key in a [`DirectIdMap`].
+///
+/// Returned by [`DirectIdMap::missing_ids`].
+/// Guaranteed to be ordered by id.
+pub struct MissingIds<'a, K: IntegerId, V> {
+    source: IterWithHoles<'a, K, V>,
+}
+impl<K: IntegerId, V> Iterator for MissingIds<'_, K, V> {
+    type Item = K::Int;
+    #[inline]
+    fn next(&mut self) -> Option<K::Int> {
+        loop {
+            if let (id, None) = self.source.next()? {
+                return Some(id);
+            }
+        }
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.source.len()))
+    }
+}
+impl<K: IntegerId, V> DoubleEndedIterator for MissingIds<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<K::Int> {
+        loop {
+            if let (id, None) = self.source.next_back()? {
+                return Some(id);
+            }
+        }
+    }
+}
+impl<K: IntegerId, V> core::iter::FusedIterator for MissingIds<'_, K, V> {}
+
+macro_rules! impl_masked_iter {
+    ($target:ident<$l:lifetime, $kt:ident, $vt:ident> -> $item_ty:ty) => {
+        impl<$l, $kt: IntegerId, $vt> Iterator for $target<$l, $kt, $vt> {
+            type Item = $item_ty;
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                loop {
+                    // the mask is ascending, so only skip forward in the table
+                    let index = self.mask.next()?;
+                    let slot = self.source.nth(index - self.next_index)?;
+                    self.next_index = index + 1;
+                    if let Some(value) = slot {
+                        // SAFETY: Value exists => index is valid
+                        let key = unsafe {
+                            $kt::from_int_unchecked(intid::uint::from_usize_wrapping(index))
+                        };
+                        return Some((key, value));
+                    }
+                }
+            }
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (0, Some(self.source.len()))
+            }
+        }
+        // Once the table or the mask is exhausted, every later call also returns `None`
+        impl<$l, $kt: IntegerId, $vt> core::iter::FusedIterator for $target<$l, $kt, $vt> {}
+    };
+}
+
+/// An iterator over the entries of a [`DirectIdMap`] whose keys are in a [`DirectIdSet`].
+///
+/// Returned by [`DirectIdMap::iter_masked`].
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct Masked<'a, K: IntegerId, V> {
+    mask: fixedbitset::Ones<'a>,
+    source: core::slice::Iter<'a, Option<V>>,
+    /// The index of the next slot in the source.
+    next_index: usize,
+    marker: PhantomData<K>,
+}
+impl_masked_iter!(Masked<'a, K, V> -> (K, &'a V));
+
+/// A mutable iterator over the entries of a [`DirectIdMap`] whose keys are in a [`DirectIdSet`].
+///
+/// Returned by [`DirectIdMap::iter_masked_mut`].
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct MaskedMut<'a, K: IntegerId, V> {
+    mask: fixedbitset::Ones<'a>,
+    source: core::slice::IterMut<'a, Option<V>>,
+    /// The index of the next slot in the source.
+    next_index: usize,
+    marker: PhantomData<K>,
+}
+impl_masked_iter!(MaskedMut<'a, K, V> -> (K, &'a mut V));
+
 /// Creates a [`DirectIdMap`] from a set of key-value pairs.
 #[macro_export]
 macro_rules! direct_idmap {
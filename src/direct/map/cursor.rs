@@ -0,0 +1,88 @@
+//! Implements a cursor over a [`DirectIdMap`], for removing entries during iteration.
+
+use super::DirectIdMap;
+use core::fmt::{self, Debug, Formatter};
+use intid::IntegerId;
+
+/// A cursor over the entries of a [`DirectIdMap`],
+/// which can remove entries while walking the map.
+///
+/// The cursor visits entries in order of the integer id of the key,
+/// and points to either an entry or the end of the map.
+///
+/// Returned by [`DirectIdMap::cursor_mut`].
+pub struct CursorMut<'a, K: IntegerId, V> {
+    map: &'a mut DirectIdMap<K, V>,
+    /// The index of the current entry,
+    /// or the length of the table if the cursor is at the end.
+    index: usize,
+}
+impl<'a, K: IntegerId, V> CursorMut<'a, K, V> {
+    #[inline]
+    pub(super) fn new(map: &'a mut DirectIdMap<K, V>) -> Self {
+        let mut cursor = CursorMut { map, index: 0 };
+        cursor.seek(0);
+        cursor
+    }
+
+    /// Move to the first entry at or after the specified index.
+    #[inline]
+    fn seek(&mut self, start: usize) {
+        self.index = match self.map.values.get(start..) {
+            Some(remaining) => remaining
+                .iter()
+                .position(Option::is_some)
+                .map_or(self.map.values.len(), |offset| start + offset),
+            None => self.map.values.len(),
+        };
+    }
+
+    /// The key of the current entry,
+    /// or `None` if the cursor is at the end of the map.
+    #[inline]
+    pub fn key(&self) -> Option<K> {
+        self.map.values.get(self.index)?.as_ref()?;
+        // SAFETY: If entry exists, the key is guaranteed to be valid
+        Some(unsafe { K::from_int_unchecked(intid::uint::from_usize_wrapping(self.index)) })
+    }
+
+    /// The current entry,
+    /// or `None` if the cursor is at the end of the map.
+    #[inline]
+    pub fn current(&mut self) -> Option<(K, &mut V)> {
+        let key = self.key()?;
+        Some((key, self.map.values[self.index].as_mut().unwrap()))
+    }
+
+    /// Move the cursor to the next entry,
+    /// or to the end of the map if there are no more entries.
+    ///
+    /// Has no effect if the cursor is already at the end.
+    #[inline]
+    pub fn advance(&mut self) {
+        if self.index < self.map.values.len() {
+            self.seek(self.index + 1);
+        }
+    }
+
+    /// Remove the current entry and move the cursor to the next one,
+    /// returning the removed entry.
+    ///
+    /// Returns `None` if the cursor is at the end of the map.
+    #[inline]
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        let key = self.key()?;
+        self.map.len -= 1;
+        let value = self.map.values[self.index].take().unwrap();
+        self.seek(self.index + 1);
+        Some((key, value))
+    }
+}
+impl<K: IntegerId, V: Debug> Debug for CursorMut<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let current = self
+            .key()
+            .map(|key| (key, self.map.values[self.index].as_ref().unwrap()));
+        f.debug_tuple("CursorMut").field(&current).finish()
+    }
+}
@@ -0,0 +1,143 @@
+//! Implements [`DefaultDirectIdMap`], where missing keys behave like a default value.
+
+use super::DirectIdMap;
+use core::fmt::{self, Debug, Formatter};
+use core::ops::{Deref, DerefMut, Index, IndexMut};
+use intid::{EquivalentId, IntegerId};
+
+/// A wrapper around a [`DirectIdMap`] where missing keys behave like a default value.
+///
+/// Reading a missing key returns the default without inserting anything,
+/// while mutably accessing a missing key inserts a copy of the default.
+/// This is useful for counters and histograms keyed by ids.
+///
+/// Everything else is delegated to the inner map through [`Deref`].
+///
+/// ## Example
+/// ```
+/// # use idmap::direct::map::DefaultDirectIdMap;
+/// let mut histogram = DefaultDirectIdMap::<u32, u32>::new();
+/// for id in [3, 7, 3] {
+///     histogram[id] += 1;
+/// }
+/// assert_eq!(histogram[3], 2);
+/// assert_eq!(histogram[5], 0);
+/// assert_eq!(histogram.len(), 2);
+/// ```
+pub struct DefaultDirectIdMap<K: IntegerId, V> {
+    map: DirectIdMap<K, V>,
+    default: V,
+}
+impl<K: IntegerId, V: Default> DefaultDirectIdMap<K, V> {
+    /// Create an empty map, using [`V::default()`](Default::default) for missing keys.
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_default(V::default())
+    }
+}
+impl<K: IntegerId, V> DefaultDirectIdMap<K, V> {
+    /// Create an empty map, using the specified value for missing keys.
+    #[inline]
+    pub fn with_default(default: V) -> Self {
+        Self::from_map(DirectIdMap::new(), default)
+    }
+
+    /// Wrap an existing map, using the specified value for missing keys.
+    #[inline]
+    pub fn from_map(map: DirectIdMap<K, V>, default: V) -> Self {
+        DefaultDirectIdMap { map, default }
+    }
+
+    /// The value used for missing keys.
+    #[inline]
+    pub fn default_value(&self) -> &V {
+        &self.default
+    }
+
+    /// Get the value associated with the specified key,
+    /// or the default value if missing.
+    ///
+    /// This never inserts anything into the map.
+    /// Unlike this method, the [`DirectIdMap::get`] inherited through [`Deref`]
+    /// returns `None` for missing keys.
+    #[inline]
+    pub fn get_or_default(&self, key: impl EquivalentId<K>) -> &V {
+        self.map.get(key).unwrap_or(&self.default)
+    }
+
+    /// Get a mutable reference to the value associated with the specified key,
+    /// inserting a copy of the default value if missing.
+    ///
+    /// See [`DirectIdMap::get_mut`] to access the value without inserting.
+    #[inline]
+    pub fn get_mut_or_insert_default(&mut self, key: K) -> &mut V
+    where
+        V: Clone,
+    {
+        let default = &self.default;
+        self.map.get_or_insert_with(key, || default.clone())
+    }
+
+    /// Unwrap the inner map, discarding the default value.
+    #[inline]
+    pub fn into_inner(self) -> DirectIdMap<K, V> {
+        self.map
+    }
+}
+impl<K: IntegerId, V: Default> Default for DefaultDirectIdMap<K, V> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<K: IntegerId, V: Default> From<DirectIdMap<K, V>> for DefaultDirectIdMap<K, V> {
+    #[inline]
+    fn from(map: DirectIdMap<K, V>) -> Self {
+        Self::from_map(map, V::default())
+    }
+}
+impl<K: IntegerId, V: Clone> Clone for DefaultDirectIdMap<K, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        DefaultDirectIdMap {
+            map: self.map.clone(),
+            default: self.default.clone(),
+        }
+    }
+}
+impl<K: IntegerId, V: Debug> Debug for DefaultDirectIdMap<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DefaultDirectIdMap")
+            .field("map", &self.map)
+            .field("default", &self.default)
+            .finish()
+    }
+}
+impl<K: IntegerId, V> Deref for DefaultDirectIdMap<K, V> {
+    type Target = DirectIdMap<K, V>;
+
+    #[inline]
+    fn deref(&self) -> &DirectIdMap<K, V> {
+        &self.map
+    }
+}
+impl<K: IntegerId, V> DerefMut for DefaultDirectIdMap<K, V> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut DirectIdMap<K, V> {
+        &mut self.map
+    }
+}
+impl<K: IntegerId, V> Index<K> for DefaultDirectIdMap<K, V> {
+    type Output = V;
+
+    #[inline]
+    fn index(&self, key: K) -> &V {
+        self.get_or_default(key)
+    }
+}
+impl<K: IntegerId, V: Clone> IndexMut<K> for DefaultDirectIdMap<K, V> {
+    #[inline]
+    fn index_mut(&mut self, key: K) -> &mut V {
+        self.get_mut_or_insert_default(key)
+    }
+}
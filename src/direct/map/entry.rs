@@ -0,0 +1,354 @@
+//! Implements the entry API for [`DirectIdMap`].
+
+use super::DirectIdMap;
+use crate::direct::oom_id;
+use core::fmt::{self, Debug, Display, Formatter};
+use intid::IntegerId;
+
+/// A view into a single entry of a [`DirectIdMap`],
+/// which may either be occupied or vacant.
+///
+/// Returned by [`DirectIdMap::entry`].
+pub enum Entry<'a, K: IntegerId, V> {
+    /// An entry which is present in the map.
+    Occupied(OccupiedEntry<'a, K, V>),
+    /// An entry which is missing from the map.
+    Vacant(VacantEntry<'a, K, V>),
+}
+impl<'a, K: IntegerId, V> Entry<'a, K, V> {
+    /// The key associated with this entry.
+    #[inline]
+    pub fn key(&self) -> K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Ensure a value is present by inserting the default if the entry is vacant,
+    /// returning a mutable reference to the value.
+    #[inline]
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Ensure a value is present by inserting the result of the function if the entry is vacant,
+    /// returning a mutable reference to the value.
+    ///
+    /// The function is only called if the entry is vacant.
+    #[inline]
+    pub fn or_insert_with<F: FnOnce() -> V>(self, func: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(func()),
+        }
+    }
+
+    /// Ensure a value is present by inserting the result of the function if the entry is vacant,
+    /// returning a mutable reference to the value.
+    ///
+    /// The function is passed the key and is only called if the entry is vacant.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::DirectIdMap;
+    /// let mut labels = DirectIdMap::<u32, String>::new();
+    /// labels.entry(7).or_insert_with_key(|id| format!("widget #{id}"));
+    /// assert_eq!(labels[7], "widget #7");
+    /// ```
+    #[inline]
+    pub fn or_insert_with_key<F: FnOnce(K) -> V>(self, func: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = func(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    /// Ensure a value is present by inserting [`V::default()`](Default::default) if the entry is vacant,
+    /// returning a mutable reference to the value.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::DirectIdMap;
+    /// let mut names = DirectIdMap::<u32, Vec<String>>::new();
+    /// names.entry(3).or_default().push("foo".into());
+    /// names.entry(3).or_default().push("bar".into());
+    /// assert_eq!(names[3], ["foo", "bar"]);
+    /// ```
+    #[inline]
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(V::default()),
+        }
+    }
+
+    /// Set the value of this entry, returning the resulting [`OccupiedEntry`].
+    ///
+    /// If the entry is already occupied, the previous value is dropped.
+    /// Use [`OccupiedEntry::insert`] to get the previous value back instead.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::DirectIdMap;
+    /// let mut map = DirectIdMap::<u32, u32>::new();
+    /// let mut entry = map.entry(3).insert_entry(7);
+    /// *entry.get_mut() += 1;
+    /// assert_eq!(entry.key(), 3);
+    /// assert_eq!(map[3], 8);
+    /// ```
+    #[inline]
+    pub fn insert_entry(self, value: V) -> OccupiedEntry<'a, K, V> {
+        match self {
+            Entry::Occupied(mut entry) => {
+                entry.insert(value);
+                entry
+            }
+            Entry::Vacant(entry) => entry.insert_entry(value),
+        }
+    }
+
+    /// Modify the value if the entry is occupied,
+    /// returning the entry for further chaining.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::DirectIdMap;
+    /// let mut counts = DirectIdMap::<u32, u32>::new();
+    /// for id in [3, 7, 3] {
+    ///     counts.entry(id).and_modify(|count| *count += 1).or_insert(1);
+    /// }
+    /// assert_eq!(counts[3], 2);
+    /// assert_eq!(counts[7], 1);
+    /// ```
+    #[inline]
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, func: F) -> Self {
+        if let Entry::Occupied(ref mut entry) = self {
+            func(entry.get_mut());
+        }
+        self
+    }
+}
+
+/// An entry in a [`DirectIdMap`] which is known to be present.
+pub struct OccupiedEntry<'a, K: IntegerId, V> {
+    pub(super) key: K,
+    /// The slot holding the value, which is guaranteed to be `Some`.
+    pub(super) slot: &'a mut Option<V>,
+    pub(super) len: &'a mut usize,
+}
+impl<'a, K: IntegerId, V> OccupiedEntry<'a, K, V> {
+    /// The key associated with this entry.
+    #[inline]
+    pub fn key(&self) -> K {
+        self.key
+    }
+
+    /// Get a reference to the value in this entry.
+    #[inline]
+    pub fn get(&self) -> &V {
+        self.slot.as_ref().unwrap()
+    }
+
+    /// Get a mutable reference to the value in this entry.
+    ///
+    /// See [`Self::into_mut`] for a reference that outlives the entry.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut V {
+        self.slot.as_mut().unwrap()
+    }
+
+    /// Convert this entry into a mutable reference to its value,
+    /// bound to the lifetime of the map.
+    #[inline]
+    pub fn into_mut(self) -> &'a mut V {
+        self.slot.as_mut().unwrap()
+    }
+
+    /// Replace the value in this entry, returning the old value.
+    #[inline]
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    /// Remove this entry from the map, returning its value.
+    #[inline]
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Remove this entry from the map, returning its key and value.
+    #[inline]
+    pub fn remove_entry(self) -> (K, V) {
+        *self.len -= 1;
+        (self.key, self.slot.take().unwrap())
+    }
+}
+
+/// An entry in a [`DirectIdMap`] which is known to be missing.
+pub struct VacantEntry<'a, K: IntegerId, V> {
+    pub(super) key: K,
+    pub(super) map: &'a mut DirectIdMap<K, V>,
+}
+impl<'a, K: IntegerId, V> VacantEntry<'a, K, V> {
+    /// The key associated with this entry.
+    #[inline]
+    pub fn key(&self) -> K {
+        self.key
+    }
+
+    /// Take ownership of the key without inserting anything.
+    #[inline]
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Insert a value into this entry,
+    /// returning a mutable reference to it.
+    #[inline]
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.insert_entry(value).into_mut()
+    }
+
+    /// Insert a value into this entry,
+    /// returning the resulting [`OccupiedEntry`].
+    #[inline]
+    pub fn insert_entry(self, value: V) -> OccupiedEntry<'a, K, V> {
+        let VacantEntry { key, map } = self;
+        let id = key.to_int();
+        let index = intid::uint::to_usize_checked(id).unwrap_or_else(|| oom_id(id));
+        map.grow_to(index);
+        let DirectIdMap { values, len, .. } = map;
+        *len += 1;
+        let slot = &mut values[index];
+        *slot = Some(value);
+        OccupiedEntry { key, slot, len }
+    }
+}
+
+/// A view into a single entry of a [`DirectIdMap`],
+/// located by its integer id rather than by key.
+///
+/// Returned by [`DirectIdMap::raw_entry_mut`].
+pub enum RawEntryMut<'a, K: IntegerId, V> {
+    /// An entry which is present in the map.
+    Occupied(OccupiedEntry<'a, K, V>),
+    /// An entry which is missing from the map.
+    Vacant(RawVacantEntry<'a, K, V>),
+}
+impl<K: IntegerId, V> RawEntryMut<'_, K, V> {
+    /// The integer id associated with this entry.
+    #[inline]
+    pub fn id(&self) -> K::Int {
+        match self {
+            RawEntryMut::Occupied(entry) => entry.key().to_int(),
+            RawEntryMut::Vacant(entry) => entry.id(),
+        }
+    }
+}
+
+/// An entry in a [`DirectIdMap`] which is known to be missing,
+/// located by its integer id rather than by key.
+pub struct RawVacantEntry<'a, K: IntegerId, V> {
+    pub(super) id: K::Int,
+    pub(super) map: &'a mut DirectIdMap<K, V>,
+}
+impl<'a, K: IntegerId, V> RawVacantEntry<'a, K, V> {
+    /// The integer id associated with this entry.
+    #[inline]
+    pub fn id(&self) -> K::Int {
+        self.id
+    }
+
+    /// Insert a value into this entry,
+    /// returning a mutable reference to it.
+    ///
+    /// ## Panics
+    /// If the id of the key doesn't match the id of the entry.
+    #[inline]
+    pub fn insert(self, key: K, value: V) -> &'a mut V {
+        assert!(
+            key.to_int() == self.id,
+            "key {key:?} does not match the id of the entry {}",
+            intid::uint::debug_desc(self.id),
+        );
+        VacantEntry { key, map: self.map }.insert(value)
+    }
+}
+
+/// The error returned by [`DirectIdMap::try_insert`] when the key is already present.
+///
+/// Contains the occupied entry and the value that was not inserted.
+pub struct OccupiedError<'a, K: IntegerId, V> {
+    /// The entry in the map that was already occupied.
+    pub entry: OccupiedEntry<'a, K, V>,
+    /// The value which was not inserted, because the entry was already occupied.
+    pub value: V,
+}
+
+impl<K: IntegerId, V: Debug> Debug for Entry<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Occupied(entry) => f.debug_tuple("Entry").field(entry).finish(),
+            Entry::Vacant(entry) => f.debug_tuple("Entry").field(entry).finish(),
+        }
+    }
+}
+impl<K: IntegerId, V: Debug> Debug for OccupiedEntry<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", &self.key)
+            .field("value", self.get())
+            .finish()
+    }
+}
+impl<K: IntegerId, V> Debug for VacantEntry<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VacantEntry").field(&self.key).finish()
+    }
+}
+impl<K: IntegerId, V: Debug> Debug for RawEntryMut<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RawEntryMut::Occupied(entry) => f.debug_tuple("RawEntryMut").field(entry).finish(),
+            RawEntryMut::Vacant(entry) => f.debug_tuple("RawEntryMut").field(entry).finish(),
+        }
+    }
+}
+impl<K: IntegerId, V> Debug for RawVacantEntry<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RawVacantEntry")
+            .field(&intid::uint::debug_desc(self.id))
+            .finish()
+    }
+}
+impl<K: IntegerId, V: Debug> Debug for OccupiedError<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", &self.entry.key)
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
+    }
+}
+impl<K: IntegerId, V: Debug> Display for OccupiedError<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value,
+            self.entry.key,
+            self.entry.get(),
+        )
+    }
+}
+impl<K: IntegerId, V: Debug> std::error::Error for OccupiedError<'_, K, V> {}
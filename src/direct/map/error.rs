@@ -0,0 +1,106 @@
+//! Errors returned by fallible [`DirectIdMap`](super::DirectIdMap) operations.
+
+use core::fmt::{self, Debug, Display, Formatter};
+use core::marker::PhantomData;
+
+use super::DirectIdMap;
+use intid::IntegerId;
+
+/// The error returned by [`DirectIdMap::from_sorted_iter`](super::DirectIdMap::from_sorted_iter)
+/// when the keys are not strictly increasing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsortedKeyError<K: IntegerId> {
+    /// The key which was out of order.
+    pub key: K,
+    /// The key which came before it, which is greater than or equal to it.
+    pub previous: K,
+}
+impl<K: IntegerId> Display for UnsortedKeyError<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "key {:?} is not greater than the previous key {:?}",
+            self.key, self.previous,
+        )
+    }
+}
+impl<K: IntegerId> std::error::Error for UnsortedKeyError<K> {}
+
+/// The error returned by [`DirectIdMap::rename_key`](super::DirectIdMap::rename_key).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RenameError<K: IntegerId> {
+    /// The key being renamed was not present in the map.
+    MissingKey(K),
+    /// The new key was already present in the map.
+    OccupiedKey(K),
+}
+impl<K: IntegerId> Display for RenameError<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::MissingKey(key) => write!(f, "key {key:?} is missing from the map"),
+            RenameError::OccupiedKey(key) => write!(f, "key {key:?} is already in the map"),
+        }
+    }
+}
+impl<K: IntegerId> std::error::Error for RenameError<K> {}
+
+/// The error returned by [`DirectIdMap::map_keys`]
+/// when two keys are mapped to the same new key.
+///
+/// No values are lost, since the error holds every entry of the original map.
+/// They are split between the entries which were already converted,
+/// the entry that collided, and the entries which were not yet visited.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DuplicateKeyError<K: IntegerId, K2: IntegerId, V> {
+    /// The new key which was produced more than once.
+    pub key: K2,
+    /// The original key of the entry which collided.
+    pub source_key: K,
+    /// The value of the entry which collided.
+    pub value: V,
+    /// The entries which were converted before the collision.
+    pub mapped: DirectIdMap<K2, V>,
+    /// The entries which were not yet converted, all with keys greater than `source_key`.
+    pub remaining: DirectIdMap<K, V>,
+}
+impl<K: IntegerId, K2: IntegerId, V: Debug> Debug for DuplicateKeyError<K, K2, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DuplicateKeyError")
+            .field("key", &self.key)
+            .field("source_key", &self.source_key)
+            .field("value", &self.value)
+            .field("mapped", &self.mapped)
+            .field("remaining", &self.remaining)
+            .finish()
+    }
+}
+impl<K: IntegerId, K2: IntegerId, V> Display for DuplicateKeyError<K, K2, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "multiple keys were mapped to {:?}", self.key)
+    }
+}
+impl<K: IntegerId, K2: IntegerId, V: Debug> std::error::Error for DuplicateKeyError<K, K2, V> {}
+
+/// The error returned by [`DirectIdMap::from_raw_slots`](super::DirectIdMap::from_raw_slots)
+/// when an occupied slot does not correspond to a valid key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidIdError<K: IntegerId> {
+    /// The index of the slot, which is not a valid id for the key type.
+    pub id: usize,
+    marker: PhantomData<K>,
+}
+impl<K: IntegerId> InvalidIdError<K> {
+    #[inline]
+    pub(super) fn new(id: usize) -> Self {
+        InvalidIdError {
+            id,
+            marker: PhantomData,
+        }
+    }
+}
+impl<K: IntegerId> Display for InvalidIdError<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "slot {} is not a valid id", self.id)
+    }
+}
+impl<K: IntegerId> std::error::Error for InvalidIdError<K> {}
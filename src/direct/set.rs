@@ -13,11 +13,12 @@ use iter::FusedIterator;
 use fixedbitset::{FixedBitSet, Ones};
 use intid::{EquivalentId, IntegerId};
 
+use super::{Word, WORD_BITS};
+
 /// A set whose members implement [IntegerId].
 ///
 /// This is implemented as a bitset,
 /// so memory is proportional to the highest integer index.
-#[derive(Clone)]
 pub struct DirectIdSet<T: IntegerId> {
     handle: FixedBitSet,
     len: usize,
@@ -38,15 +39,29 @@ impl<T: IntegerId> DirectIdSet<T> {
     ///
     /// Since this is a direct set,
     /// this hints at the maximum valid id and not the length.
+    /// The capacity is rounded up to a whole number of allocation blocks.
+    /// A capacity of zero does not allocate.
     #[inline]
     pub fn with_capacity(max_id: usize) -> Self {
         DirectIdSet {
-            handle: FixedBitSet::with_capacity(max_id),
+            handle: allocate_bits(max_id),
             len: 0,
             marker: PhantomData,
         }
     }
 
+    /// Initialize the set with space for every id up to and including that of `key`.
+    ///
+    /// Unlike [`Self::with_capacity`], this makes the meaning of the capacity explicit.
+    ///
+    /// ## Panics
+    /// If the id is so large that the set would exhaust memory.
+    #[inline]
+    #[track_caller]
+    pub fn with_max_key(key: T) -> Self {
+        Self::with_capacity(super::slots_for_max_id(key.to_int()))
+    }
+
     /// Inserts the specified element into the set,
     /// returning `true` if it was already in the set and `false` if it wasn't.
     #[inline]
@@ -54,8 +69,10 @@ impl<T: IntegerId> DirectIdSet<T> {
         let value = value.to_int();
         let index: usize =
             intid::uint::to_usize_checked(value).unwrap_or_else(|| super::oom_id(value));
-        let was_present = self.handle.contains(index);
-        self.handle.grow_and_insert(index);
+        if index >= self.handle.len() {
+            self.grow_fallback(index);
+        }
+        let was_present = self.handle.put(index);
         if !was_present {
             self.len += 1;
         }
@@ -107,6 +124,121 @@ impl<T: IntegerId> DirectIdSet<T> {
         }
     }
 
+    /// Debug the set using the integer ids of its values,
+    /// collapsing consecutive ids into ranges.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::direct_idset;
+    /// let set = direct_idset![0u32, 1, 2, 3, 7, 10, 11];
+    /// assert_eq!(format!("{:?}", set.debug_ids()), "{0..=3, 7, 10..=11}");
+    /// ```
+    #[inline]
+    pub fn debug_ids(&self) -> DebugIds<'_, T> {
+        DebugIds { set: self }
+    }
+
+    /// Iterate over the values present in either set.
+    ///
+    /// Guaranteed to be ordered by the integer value of the key.
+    /// The bitsets are combined a word at a time,
+    /// treating the shorter one as if it were padded with zeros.
+    ///
+    /// ## Example
+    /// ```
+    /// # use idmap::direct_idset;
+    /// let small = direct_idset![1u32, 3];
+    /// let large = direct_idset![3u32, 200];
+    /// assert_eq!(small.union(&large).collect::<Vec<_>>(), [1, 3, 200]);
+    /// ```
+    #[inline]
+    pub fn union<'a>(&'a self, other: &'a DirectIdSet<T>) -> Union<'a, T> {
+        Union {
+            left: self.handle.as_slice(),
+            right: other.handle.as_slice(),
+            word_index: 0,
+            current: self.word_at(0) | other.word_at(0),
+            marker: PhantomData,
+        }
+    }
+
+    /// Create a new set containing the values present in either set.
+    ///
+    /// See [`Self::union`] to iterate over them without allocating.
+    pub fn union_set(&self, other: &DirectIdSet<T>) -> DirectIdSet<T> {
+        let (shorter, longer) = if self.handle.len() <= other.handle.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut handle = longer.handle.clone();
+        handle.union_with(&shorter.handle);
+        DirectIdSet {
+            len: handle.count_ones(..),
+            handle,
+            marker: PhantomData,
+        }
+    }
+
+    /// The word of the bitset at the specified index,
+    /// or zero if past the end.
+    #[inline]
+    fn word_at(&self, word_index: usize) -> Word {
+        self.handle.as_slice().get(word_index).copied().unwrap_or(0)
+    }
+
+    /// The underlying bitset, indexed by integer id.
+    #[inline]
+    pub(crate) fn bits(&self) -> &FixedBitSet {
+        &self.handle
+    }
+
+    /// The number of ids the set can hold without reallocating.
+    ///
+    /// Since this is a direct set,
+    /// this refers to the maximum id rather than the number of entries.
+    /// Any value whose integer id is less than the capacity can be inserted without allocating.
+    ///
+    /// This is the size of the allocated bitset in bits,
+    /// which is always a whole number of allocation blocks.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.handle.len()
+    }
+
+    /// Grow the bitset to fit the specified index,
+    /// at least doubling the capacity to amortize repeated growth.
+    #[cold]
+    fn grow_fallback(&mut self, index: usize) {
+        let needed = index.checked_add(1).unwrap_or_else(|| super::oom_id(index));
+        let mut grown = allocate_bits(needed.max(self.capacity().saturating_mul(2)));
+        let old_words = self.handle.as_slice();
+        grown.as_mut_slice()[..old_words.len()].copy_from_slice(old_words);
+        self.handle = grown;
+    }
+
+    /// The number of bytes of heap memory allocated by the set.
+    ///
+    /// Like [`DirectIdMap::allocated_bytes`](super::DirectIdMap::allocated_bytes),
+    /// this is based on the [capacity](Self::capacity) rather than the ids in use.
+    #[inline]
+    pub fn allocated_bytes(&self) -> usize {
+        self.capacity() / WORD_BITS * core::mem::size_of::<Word>()
+    }
+
+    /// Trim unused capacity past the largest id in the set.
+    ///
+    /// The capacity is still rounded up to a whole number of allocation blocks.
+    pub fn shrink_to_fit(&mut self) {
+        let needed = self.handle.maximum().map_or(0, |max| max + 1);
+        let mut shrunk = allocate_bits(needed);
+        if shrunk.len() < self.capacity() {
+            let words = shrunk.as_mut_slice();
+            words.copy_from_slice(&self.handle.as_slice()[..words.len()]);
+            self.handle = shrunk;
+        }
+    }
+
     /// Clear the values in this set
     #[inline]
     pub fn clear(&mut self) {
@@ -134,7 +266,7 @@ impl<T: IntegerId> DirectIdSet<T> {
     pub fn retain<F: FnMut(T) -> bool>(&mut self, mut func: F) {
         for (word_index, word) in self.handle.as_mut_slice().iter_mut().enumerate() {
             let (updated_word, word_removed) = retain_word(*word, |bit| {
-                let id = (word_index * 32) + (bit as usize);
+                let id = (word_index * WORD_BITS) + (bit as usize);
                 // Safety: If present in the map, it is known to be valid
                 let key = unsafe { T::from_int_unchecked(intid::uint::from_usize_wrapping(id)) };
                 func(key)
@@ -144,8 +276,23 @@ impl<T: IntegerId> DirectIdSet<T> {
         }
     }
 }
-/// The type of a word in a [`FixedBitSet`].
-type Word = fixedbitset::Block;
+/// The granularity of bitset allocations, in bits.
+///
+/// Depending on the target, [`FixedBitSet`] allocates SIMD blocks of up to 256 bits.
+/// Keeping the length of the bitset a multiple of this
+/// ensures the length matches the size of the allocation.
+const ALLOCATION_BITS: usize = 256;
+
+/// Allocate a cleared bitset with room for at least the specified number of bits,
+/// rounded up to a multiple of [`ALLOCATION_BITS`].
+#[inline]
+fn allocate_bits(bits: usize) -> FixedBitSet {
+    let blocks = bits / ALLOCATION_BITS + usize::from(bits % ALLOCATION_BITS != 0);
+    let bits = blocks
+        .checked_mul(ALLOCATION_BITS)
+        .unwrap_or_else(|| super::oom_id(bits));
+    FixedBitSet::with_capacity(bits)
+}
 #[inline]
 fn retain_word<F: FnMut(u32) -> bool>(original_word: Word, mut func: F) -> (Word, u32) {
     let mut remaining = original_word;
@@ -161,9 +308,36 @@ fn retain_word<F: FnMut(u32) -> bool>(original_word: Word, mut func: F) -> (Word
         }
         remaining &= !mask;
     }
-    debug_assert!(removed <= 32);
+    debug_assert!(removed <= Word::BITS);
     (result, removed)
 }
+impl<T: IntegerId> Clone for DirectIdSet<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        DirectIdSet {
+            handle: self.handle.clone(),
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    /// Reuse the existing allocation when cloning into an existing set.
+    ///
+    /// If the existing allocation is large enough, the capacity is kept.
+    #[inline]
+    fn clone_from(&mut self, source: &Self) {
+        let source_words = source.handle.as_slice();
+        let words = self.handle.as_mut_slice();
+        if words.len() >= source_words.len() {
+            let (head, tail) = words.split_at_mut(source_words.len());
+            head.copy_from_slice(source_words);
+            tail.fill(0);
+        } else {
+            self.handle = source.handle.clone();
+        }
+        self.len = source.len;
+    }
+}
 impl<T: IntegerId> Default for DirectIdSet<T> {
     #[inline]
     fn default() -> Self {
@@ -173,7 +347,15 @@ impl<T: IntegerId> Default for DirectIdSet<T> {
 impl<T: IntegerId> PartialEq for DirectIdSet<T> {
     #[inline]
     fn eq(&self, other: &DirectIdSet<T>) -> bool {
-        self.len == other.len && self.handle == other.handle
+        // FixedBitSet equality also compares capacity, so treat missing words as zero
+        let (shorter, longer) = if self.handle.len() <= other.handle.len() {
+            (self.handle.as_slice(), other.handle.as_slice())
+        } else {
+            (other.handle.as_slice(), self.handle.as_slice())
+        };
+        self.len == other.len
+            && shorter == &longer[..shorter.len()]
+            && longer[shorter.len()..].iter().all(|&word| word == 0)
     }
 }
 impl<T: IntegerId> Eq for DirectIdSet<T> {}
@@ -331,6 +513,31 @@ macro_rules! do_impl_iter {
         impl<T: IntegerId> FusedIterator for $target<$($lt,)* T> {}
     };
 }
+/// Debugs a [`DirectIdSet`] using the integer ids of its values.
+///
+/// Returned by [`DirectIdSet::debug_ids`].
+pub struct DebugIds<'a, T: IntegerId> {
+    set: &'a DirectIdSet<T>,
+}
+impl<T: IntegerId> Debug for DebugIds<'_, T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut res = f.debug_set();
+        let mut ones = self.set.handle.ones().peekable();
+        while let Some(start) = ones.next() {
+            let mut end = start;
+            while ones.next_if_eq(&(end + 1)).is_some() {
+                end += 1;
+            }
+            if start == end {
+                res.entry(&format_args!("{start}"));
+            } else {
+                res.entry(&format_args!("{start}..={end}"));
+            }
+        }
+        res.finish()
+    }
+}
+
 /// An iterator over the values in an [DirectIdSet].
 ///
 /// TODO: Cannot implement `Clone` because [`fixedbitset::Ones`] doesn't support it yet.
@@ -355,6 +562,51 @@ pub struct IntoIter<T: IntegerId> {
 }
 do_impl_iter!(IntoIter);
 
+/// An iterator over the values present in either of two [`DirectIdSet`]s.
+///
+/// Returned by [`DirectIdSet::union`].
+/// Guaranteed to be ordered by the integer value of the key.
+pub struct Union<'a, T: IntegerId> {
+    left: &'a [Word],
+    right: &'a [Word],
+    word_index: usize,
+    /// The bits of the current word which have not yet been yielded.
+    current: Word,
+    marker: PhantomData<T>,
+}
+impl<T: IntegerId> Iterator for Union<'_, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        while self.current == 0 {
+            self.word_index += 1;
+            if self.word_index >= self.left.len().max(self.right.len()) {
+                return None;
+            }
+            let word = |words: &[Word]| words.get(self.word_index).copied().unwrap_or(0);
+            self.current = word(self.left) | word(self.right);
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1; // clear lowest bit
+        let index = self.word_index * WORD_BITS + bit;
+        // SAFETY: Id is present in one of the sets => id is valid
+        Some(unsafe { T::from_int_unchecked(intid::uint::from_usize_wrapping(index)) })
+    }
+}
+impl<T: IntegerId> FusedIterator for Union<'_, T> {}
+impl<T: IntegerId> Clone for Union<'_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Union { ..*self }
+    }
+}
+impl<T: IntegerId> Debug for Union<'_, T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
 #[cfg(feature = "petgraph")]
 impl<T: IntegerId> petgraph::visit::VisitMap<T> for DirectIdSet<T> {
     #[inline]
@@ -0,0 +1,27 @@
+#![allow(missing_docs)]
+use intid::{ContiguousIntegerId, IntegerId, IntegerIdCounter};
+
+#[derive(IntegerId, Debug, Copy, Clone, PartialEq, Eq)]
+#[intid(contiguous, counter)]
+struct TupleId(u8);
+
+#[derive(IntegerId, Debug, Copy, Clone, PartialEq, Eq)]
+#[intid(contiguous)]
+struct NamedId {
+    index: u16,
+}
+
+#[test]
+fn test_contiguous_bounds() {
+    assert_eq!(TupleId::MIN_ID, TupleId(0));
+    assert_eq!(TupleId::MAX_ID, TupleId(u8::MAX));
+    assert_eq!(TupleId::START, TupleId(0));
+    assert_eq!(NamedId::MIN_ID, NamedId { index: 0 });
+    assert_eq!(NamedId::MAX_ID, NamedId { index: u16::MAX });
+}
+
+#[test]
+fn test_newtype_roundtrip() {
+    assert_eq!(TupleId::from_int(7).to_int(), 7);
+    assert_eq!(NamedId::from_int_checked(300), Some(NamedId { index: 300 }));
+}
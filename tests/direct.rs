@@ -1,4 +1,6 @@
 #![allow(missing_docs)]
+extern crate alloc;
+
 use intid::IntegerId;
 
 use itertools::Itertools;
@@ -6,7 +8,16 @@ use serde_derive::{Deserialize, Serialize};
 #[cfg(feature = "serde")]
 use serde_test::{assert_tokens, Token};
 
-use idmap::{direct_idmap, DirectIdMap};
+use alloc::collections::BTreeMap;
+use core::cell::Cell;
+use core::hash::{Hash, Hasher};
+use core::ops::Bound;
+use idmap::direct::map::{
+    DefaultDirectIdMap, DuplicateKeyError, Entry, PatchSummary, Range as DirectIdMapRange,
+    RawEntryMut, RenameError,
+};
+use idmap::{direct_idmap, DirectIdMap, DirectIdSet};
+use std::collections::HashMap;
 use KnownState::*;
 
 #[test]
@@ -30,6 +41,19 @@ fn test_eq() {
         .collect::<DirectIdMap<_, _>>();
 
     assert_eq!(first, second);
+
+    // trailing empty slots don't affect equality
+    let mut third = important_cities();
+    third.insert(NorthDakota, "Fargo");
+    third.remove(NorthDakota);
+    assert_eq!(first, third);
+    assert_eq!(third, first);
+    let mut empty = DirectIdMap::new();
+    empty.insert(NorthDakota, "Fargo");
+    empty.remove(NorthDakota);
+    assert_eq!(empty, DirectIdMap::new());
+    third.remove(Arizona);
+    assert_ne!(first, third);
 }
 
 #[test]
@@ -55,6 +79,21 @@ fn test_clone() {
     assert_eq!(original, cloned);
 }
 
+#[test]
+fn test_clone_from() {
+    let original = important_cities();
+    let mut scratch = DirectIdMap::new();
+    scratch.reserve(100);
+    scratch.insert(NorthDakota, "Fargo");
+    let capacity = scratch.capacity();
+    for _ in 0..3 {
+        scratch.clone_from(&original);
+        assert_eq!(scratch, original);
+        assert_eq!(scratch.len(), 3);
+        assert_eq!(scratch.capacity(), capacity);
+    }
+}
+
 #[test]
 fn test_index() {
     let map = important_cities();
@@ -95,7 +134,6 @@ fn test_index_nonexistent() {
 }
 
 #[test]
-#[cfg(any())] // TODO: Support entry API?
 fn test_entry_insert() {
     let mut map = important_cities();
 
@@ -136,6 +174,1860 @@ fn test_retain() {
     check_missing(TINY_STATES, &map);
 }
 
+#[test]
+fn test_drain() {
+    let mut map = important_cities();
+    let drained = map.drain().collect::<Vec<_>>();
+    assert_eq!(
+        drained,
+        vec![
+            (Arizona, "Phoenix"),
+            (California, "Los Angeles"),
+            (NewYork, "New York City")
+        ]
+    );
+    assert_eq!(map.len(), 0);
+    check_missing(ALL_STATES, &map);
+    // partially consuming the iterator still clears the map
+    let mut map = important_cities();
+    let mut drain = map.drain();
+    assert_eq!(drain.len(), 3);
+    assert_eq!(drain.next(), Some((Arizona, "Phoenix")));
+    drop(drain);
+    assert!(map.is_empty());
+    check_missing(ALL_STATES, &map);
+    // the map can be reused afterwards
+    map.insert(NorthDakota, "Fargo");
+    check_cities(&[NorthDakota], &map);
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn test_extract_if() {
+    let mut map = important_cities();
+    map.insert(NorthDakota, "Fargo");
+    let extracted = map
+        .extract_if(|state, _| matches!(state, California | NorthDakota))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        extracted,
+        vec![(California, "Los Angeles"), (NorthDakota, "Fargo")]
+    );
+    assert_eq!(map.len(), 2);
+    check_cities(&[Arizona, NewYork], &map);
+    check_missing(&[California, NewMexico, NorthDakota], &map);
+}
+
+#[test]
+fn test_extract_if_partial() {
+    let mut map = important_cities();
+    map.insert(NorthDakota, "Fargo");
+    let mut extract = map.extract_if(|state, _| state != NewYork);
+    assert_eq!(extract.next(), Some((Arizona, "Phoenix")));
+    drop(extract);
+    // remaining matches are removed on drop
+    assert_eq!(map.len(), 1);
+    check_cities(&[NewYork], &map);
+    check_missing(&[Arizona, California, NewMexico, NorthDakota], &map);
+}
+
+#[test]
+fn test_extract_if_panic() {
+    let mut map = important_cities();
+    map.insert(NorthDakota, "Fargo");
+    let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+        map.extract_if(|state, _| match state {
+            Arizona => true,
+            NewYork => panic!("too big"),
+            _ => true,
+        })
+        .for_each(drop);
+    }));
+    assert!(result.is_err());
+    // entries before the panic are removed, entries after it are untouched
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.iter().count(), 2);
+    check_cities(&[NewYork, NorthDakota], &map);
+    check_missing(&[Arizona, California, NewMexico], &map);
+}
+
+#[test]
+fn test_keys_values() {
+    let mut map = important_cities();
+    assert_eq!(
+        map.keys().collect::<Vec<_>>(),
+        vec![Arizona, California, NewYork]
+    );
+    assert_eq!(
+        map.values().copied().collect::<Vec<_>>(),
+        vec!["Phoenix", "Los Angeles", "New York City"]
+    );
+    for value in map.values_mut() {
+        *value = "Springfield";
+    }
+    assert!(map.values().all(|&city| city == "Springfield"));
+    assert_eq!(map.len(), 3);
+}
+
+#[test]
+fn test_into_keys_values() {
+    let mut map = important_cities();
+    map.insert(NorthDakota, "Fargo");
+    map.remove(California);
+    let keys = map.clone().into_keys();
+    assert_eq!(keys.len(), 3);
+    assert_eq!(
+        keys.collect::<Vec<_>>(),
+        vec![Arizona, NewYork, NorthDakota]
+    );
+    let values = map.into_values();
+    assert_eq!(values.len(), 3);
+    assert_eq!(
+        values.collect::<Vec<_>>(),
+        vec!["Phoenix", "New York City", "Fargo"]
+    );
+}
+
+#[test]
+fn test_get_many_mut() {
+    let mut map = important_cities();
+    let [arizona, new_york] = map.get_many_mut([Arizona, NewYork]).unwrap();
+    core::mem::swap(arizona, new_york);
+    assert_eq!(map[Arizona], "New York City");
+    assert_eq!(map[NewYork], "Phoenix");
+    assert_eq!(map.get_many_mut([California, NewMexico]), None);
+    assert_eq!(map.get_many_mut([NorthDakota]), None);
+    assert_eq!(map.get_many_mut([California, Arizona, California]), None);
+    assert_eq!(map.get_many_mut([] as [KnownState; 0]), Some([]));
+    assert_eq!(map.len(), 3);
+}
+
+#[test]
+fn test_entry_occupied() {
+    let mut map = important_cities();
+    match map.entry(Arizona) {
+        Entry::Occupied(mut entry) => {
+            assert_eq!(entry.key(), Arizona);
+            assert_eq!(*entry.get(), "Phoenix");
+            assert_eq!(entry.insert("Tucson"), "Phoenix");
+            assert_eq!(entry.remove(), "Tucson");
+        }
+        Entry::Vacant(_) => unreachable!(),
+    }
+    assert_eq!(map.len(), 2);
+    check_missing(&[Arizona], &map);
+    assert!(matches!(map.entry(Arizona), Entry::Vacant(_)));
+}
+
+#[test]
+fn test_entry_key() {
+    let mut map = important_cities();
+    assert_eq!(map.entry(Arizona).key(), Arizona);
+    let entry = map.entry(NewMexico);
+    assert_eq!(entry.key(), NewMexico);
+    match entry {
+        Entry::Vacant(entry) => {
+            assert_eq!(entry.key(), NewMexico);
+            assert_eq!(entry.into_key(), NewMexico);
+        }
+        Entry::Occupied(_) => unreachable!(),
+    }
+    assert_eq!(map.len(), 3);
+    check_missing(&[NewMexico], &map);
+}
+
+#[test]
+fn test_remove_entry() {
+    let mut map = important_cities();
+    assert_eq!(map.remove_entry(NewMexico), None);
+    assert_eq!(map.remove_entry(Arizona), Some((Arizona, "Phoenix")));
+    assert_eq!(map.remove_entry(Arizona), None);
+    match map.entry(California) {
+        Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), (California, "Los Angeles")),
+        Entry::Vacant(_) => unreachable!(),
+    }
+    assert_eq!(map.len(), 1);
+    check_cities(&[NewYork], &map);
+}
+
+#[test]
+fn test_try_insert() -> Result<(), String> {
+    let mut map = important_cities();
+    *map.try_insert(NorthDakota, "Bismarck")
+        .map_err(|err| err.to_string())? = "Fargo";
+    let err = map.try_insert(Arizona, "Tucson").unwrap_err();
+    assert_eq!(err.entry.key(), Arizona);
+    assert_eq!(*err.entry.get(), "Phoenix");
+    assert_eq!(err.value, "Tucson");
+    assert_eq!(
+        err.to_string(),
+        "failed to insert \"Tucson\", key Arizona already exists with value \"Phoenix\""
+    );
+    assert_eq!(map.len(), 4);
+    check_cities(&[Arizona, NorthDakota], &map);
+    Ok(())
+}
+
+#[test]
+fn test_insert_unique_unchecked() {
+    let mut map = DirectIdMap::new();
+    for state in IMPORTANT_STATES {
+        let (key, city) = map.insert_unique_unchecked(*state, "");
+        assert_eq!(key, *state);
+        *city = state.city();
+    }
+    assert_eq!(map, important_cities());
+    assert_eq!(map.len(), 3);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "key already present: Arizona")]
+fn test_insert_unique_unchecked_duplicate() {
+    let mut map = important_cities();
+    map.insert_unique_unchecked(Arizona, "Tucson");
+}
+
+#[test]
+fn test_raw_entry_mut() {
+    let mut cities = important_cities();
+    match cities.raw_entry_mut(NewYork.to_int()) {
+        RawEntryMut::Occupied(mut entry) => {
+            assert_eq!(entry.key(), NewYork);
+            *entry.get_mut() = "NYC";
+        }
+        RawEntryMut::Vacant(_) => unreachable!(),
+    }
+    assert_eq!(cities[NewYork], "NYC");
+    // not a valid KnownState
+    assert!(matches!(cities.raw_entry_mut(17), RawEntryMut::Vacant(_)));
+    assert_eq!(cities.raw_entry_mut(usize::MAX).id(), usize::MAX);
+    match cities.raw_entry_mut(NorthDakota.to_int()) {
+        RawEntryMut::Occupied(_) => unreachable!(),
+        RawEntryMut::Vacant(entry) => {
+            assert_eq!(entry.id(), NorthDakota.to_int());
+            assert_eq!(*entry.insert(NorthDakota, "Fargo"), "Fargo");
+        }
+    }
+    assert_eq!(cities.len(), 4);
+    assert_eq!(cities[NorthDakota], "Fargo");
+    match cities.raw_entry_mut(California.to_int()) {
+        RawEntryMut::Occupied(entry) => assert_eq!(entry.remove(), "Los Angeles"),
+        RawEntryMut::Vacant(_) => unreachable!(),
+    }
+    assert_eq!(cities.len(), 3);
+}
+
+#[test]
+#[should_panic(expected = "does not match the id of the entry")]
+fn test_raw_entry_mismatched_key() {
+    let mut cities = important_cities();
+    if let RawEntryMut::Vacant(entry) = cities.raw_entry_mut(NewMexico.to_int()) {
+        entry.insert(NorthDakota, "Fargo");
+    }
+}
+
+#[test]
+fn test_first_last_entry() {
+    let mut cities = important_cities();
+    let mut first = cities.first_entry().unwrap();
+    assert_eq!(first.key(), Arizona);
+    assert_eq!(first.insert("Tucson"), "Phoenix");
+    assert_eq!(cities[Arizona], "Tucson");
+    let mut last = cities.last_entry().unwrap();
+    assert_eq!(last.key(), NewYork);
+    *last.get_mut() = "NYC";
+    assert_eq!(cities[NewYork], "NYC");
+    assert_eq!(cities.last_entry().unwrap().remove(), "NYC");
+    assert_eq!(cities.max_id(), Some(California.to_int()));
+    assert_eq!(cities.len(), 2);
+    assert_eq!(
+        cities.first_entry().unwrap().remove_entry(),
+        (Arizona, "Tucson")
+    );
+    assert_eq!(cities.min_id(), Some(California.to_int()));
+    cities.last_entry().unwrap().remove();
+    assert!(cities.first_entry().is_none());
+    assert!(cities.last_entry().is_none());
+    assert!(cities.is_empty());
+}
+
+#[test]
+fn test_entry_insert_entry() {
+    let mut cities = important_cities();
+    let entry = cities.entry(Arizona).insert_entry("Tucson");
+    assert_eq!(entry.key(), Arizona);
+    assert_eq!(*entry.get(), "Tucson");
+    let mut entry = cities.entry(NorthDakota).insert_entry("Bismarck");
+    assert_eq!(*entry.get(), "Bismarck");
+    *entry.get_mut() = "Fargo";
+    assert_eq!(cities.len(), 4);
+    assert_eq!(cities[Arizona], "Tucson");
+    assert_eq!(cities[NorthDakota], "Fargo");
+    assert_eq!(
+        cities.entry(NewMexico).insert_entry("Albuquerque").remove(),
+        "Albuquerque"
+    );
+    assert_eq!(cities.len(), 4);
+}
+
+#[test]
+fn test_entry_and_modify() {
+    let mut map = important_cities();
+    map.entry(Arizona)
+        .and_modify(|city| *city = "Tucson")
+        .or_insert("Flagstaff");
+    map.entry(NorthDakota)
+        .and_modify(|city| *city = "Bismarck")
+        .or_insert("Fargo");
+    assert_eq!(map[Arizona], "Tucson");
+    assert_eq!(map[NorthDakota], "Fargo");
+    assert_eq!(map.len(), 4);
+}
+
+#[test]
+fn test_shrink() {
+    let mut map = important_cities();
+    map.insert(NorthDakota, "Fargo");
+    map.remove(NorthDakota);
+    map.shrink_to(0);
+    assert_eq!(map, important_cities());
+    map.remove(NewYork);
+    let old_capacity = map.capacity();
+    map.shrink_to_fit();
+    assert!(map.capacity() < old_capacity);
+    assert_eq!(map.len(), 2);
+    check_cities(&[Arizona, California], &map);
+    check_missing(&[NewYork, NorthDakota], &map);
+}
+
+#[test]
+fn test_capacity() {
+    let mut map = DirectIdMap::<u32, &'static str>::new();
+    assert_eq!(map.capacity(), 0);
+    map.insert(99, "foo");
+    assert!(map.capacity() >= 100);
+    let capacity = map.capacity();
+    map.clear();
+    assert_eq!(map.capacity(), capacity);
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_reserve_exact() {
+    let mut amortized = DirectIdMap::<u32, &'static str>::new();
+    amortized.insert(9, "foo");
+    let mut exact = amortized.clone();
+    amortized.reserve(10);
+    exact.reserve_exact(10);
+    assert!(amortized.capacity() >= 20);
+    assert!(exact.capacity() >= 20);
+    assert!(exact.capacity() <= amortized.capacity());
+    let capacity = exact.capacity();
+    exact.insert(19, "bar");
+    assert_eq!(exact.capacity(), capacity);
+}
+
+#[test]
+fn test_allocated_bytes() {
+    let mut map = DirectIdMap::<u32, u64>::new();
+    assert_eq!(map.allocated_bytes(), 0);
+    let mut last_size = 0;
+    for id in [3, 17, 200, 1000] {
+        map.insert(id, 0);
+        assert!(map.allocated_bytes() > last_size);
+        last_size = map.allocated_bytes();
+    }
+    map.remove(1000);
+    map.shrink_to_fit();
+    assert!(map.allocated_bytes() < last_size);
+    assert_eq!(
+        map.allocated_bytes(),
+        map.capacity() * core::mem::size_of::<Option<u64>>()
+    );
+}
+
+#[test]
+fn test_pop_last() {
+    let mut map = important_cities();
+    assert_eq!(map.max_id(), Some(NewYork.to_int()));
+    assert_eq!(map.pop_last(), Some((NewYork, "New York City")));
+    // skips over the hole left by NewMexico
+    assert_eq!(map.max_id(), Some(California.to_int()));
+    map.insert(NorthDakota, "Fargo");
+    map.remove(NorthDakota);
+    assert_eq!(map.max_id(), Some(California.to_int()));
+    assert_eq!(map.pop_last(), Some((California, "Los Angeles")));
+    assert_eq!(map.pop_last(), Some((Arizona, "Phoenix")));
+    assert_eq!(map.max_id(), None);
+    assert_eq!(map.pop_last(), None);
+    assert!(map.is_empty());
+
+    let mut queue = [5u32, 1, 900, 42, 7]
+        .into_iter()
+        .map(|id| (id, id * 2))
+        .collect::<DirectIdMap<u32, u32>>();
+    let mut popped = Vec::new();
+    while let Some((id, value)) = queue.pop_last() {
+        assert_eq!(value, id * 2);
+        popped.push(id);
+    }
+    assert_eq!(popped, vec![900, 42, 7, 5, 1]);
+}
+
+#[test]
+fn test_pop_first() {
+    let mut map = DirectIdMap::<u64, &'static str>::new();
+    assert_eq!(map.min_id(), None);
+    assert_eq!(map.pop_first(), None);
+    for (id, job) in [(8, "eight"), (3, "three"), (12, "twelve"), (5, "five")] {
+        map.insert(id, job);
+    }
+    assert_eq!(map.min_id(), Some(3));
+    assert_eq!(map.pop_first(), Some((3, "three")));
+    assert_eq!(map.min_id(), Some(5));
+    // removal in the middle
+    map.remove(8);
+    assert_eq!(map.pop_first(), Some((5, "five")));
+    assert_eq!(map.pop_first(), Some((12, "twelve")));
+    assert_eq!(map.pop_first(), None);
+    assert_eq!(map.min_id(), None);
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_first_last_key_value() {
+    let mut map = important_cities();
+    assert_eq!(map.first_key_value(), Some((Arizona, &"Phoenix")));
+    assert_eq!(map.last_key_value(), Some((NewYork, &"New York City")));
+    *map.first_value_mut().unwrap() = "Tucson";
+    *map.last_value_mut().unwrap() = "Buffalo";
+    assert_eq!(map[Arizona], "Tucson");
+    assert_eq!(map[NewYork], "Buffalo");
+    // removals change the extremes
+    map.remove(Arizona);
+    map.remove(NewYork);
+    assert_eq!(map.first_key_value(), Some((California, &"Los Angeles")));
+    assert_eq!(map.last_key_value(), Some((California, &"Los Angeles")));
+    map.remove(California);
+    assert_eq!(map.first_key_value(), None);
+    assert_eq!(map.last_key_value(), None);
+    assert_eq!(map.first_value_mut(), None);
+    assert_eq!(map.last_value_mut(), None);
+}
+
+#[test]
+fn test_range() {
+    let map = [1u32, 4, 5, 9, 20]
+        .into_iter()
+        .map(|id| (id, id * 10))
+        .collect::<DirectIdMap<u32, u32>>();
+    let keys = |range: DirectIdMapRange<'_, u32, u32>| range.map(|(id, _)| id).collect_vec();
+    assert_eq!(keys(map.range(4..9)), vec![4, 5]);
+    assert_eq!(keys(map.range(4..=9)), vec![4, 5, 9]);
+    assert_eq!(keys(map.range(..5)), vec![1, 4]);
+    assert_eq!(keys(map.range(5..)), vec![5, 9, 20]);
+    assert_eq!(keys(map.range(..)), vec![1, 4, 5, 9, 20]);
+    assert_eq!(
+        keys(map.range((Bound::Excluded(4), Bound::Included(20)))),
+        vec![5, 9, 20]
+    );
+    assert_eq!(keys(map.range(6..9)), vec![]);
+    assert_eq!(keys(map.range(5..5)), vec![]);
+    #[allow(clippy::reversed_empty_ranges)]
+    let backwards = map.range(9..5);
+    assert_eq!(keys(backwards), vec![]);
+    assert_eq!(keys(map.range(21..1000)), vec![]);
+    assert_eq!(keys(map.range(u32::MAX..)), vec![]);
+    assert_eq!(keys(map.range(10..u32::MAX)), vec![20]);
+    assert_eq!(
+        map.range(2..=9).rev().collect_vec(),
+        vec![(9, &90), (5, &50), (4, &40)]
+    );
+}
+
+#[test]
+fn test_range_mut() {
+    let mut map = important_cities();
+    for (_, city) in map.range_mut(California..) {
+        *city = "Springfield";
+    }
+    assert_eq!(map[Arizona], "Phoenix");
+    assert_eq!(map[California], "Springfield");
+    assert_eq!(map[NewYork], "Springfield");
+    assert_eq!(map.range_mut(NewMexico..NewYork).count(), 0);
+}
+
+#[test]
+fn test_split_off() {
+    let mut map = important_cities();
+    map.insert(NorthDakota, "Fargo");
+    let capacity = map.capacity();
+    let high = map.split_off(NewMexico);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.capacity(), capacity);
+    assert_eq!(map.max_id(), Some(California.to_int()));
+    check_cities(&[Arizona, California], &map);
+    check_missing(&[NewMexico, NewYork, NorthDakota], &map);
+    assert_eq!(high.len(), 2);
+    assert_eq!(high.min_id(), Some(NewYork.to_int()));
+    check_cities(&[NewYork, NorthDakota], &high);
+    check_missing(&[Arizona, California], &high);
+
+    // splitting past the largest key moves nothing
+    let mut map = important_cities();
+    assert!(map.split_off(NorthDakota).is_empty());
+    assert_eq!(map, important_cities());
+    // splitting at zero moves everything
+    let everything = map.split_off(Arizona);
+    assert!(map.is_empty());
+    assert_eq!(everything, important_cities());
+}
+
+#[test]
+fn test_append() {
+    // other has larger keys
+    let mut map = direct_idmap! {
+        Arizona => "Phoenix",
+        California => "San Diego",
+    };
+    let mut other = direct_idmap! {
+        California => "Los Angeles",
+        NewYork => "New York City",
+    };
+    let capacity = other.capacity();
+    map.append(&mut other);
+    assert_eq!(map, important_cities());
+    assert!(other.is_empty());
+    assert_eq!(other.capacity(), capacity);
+    check_missing(ALL_STATES, &other);
+
+    // other has smaller keys
+    let mut map = direct_idmap! {
+        NorthDakota => "Fargo",
+    };
+    map.append(&mut important_cities());
+    assert_eq!(map.len(), 4);
+    check_cities(&[Arizona, California, NewYork, NorthDakota], &map);
+
+    // appending an empty map
+    let mut map = important_cities();
+    map.append(&mut DirectIdMap::new());
+    assert_eq!(map, important_cities());
+}
+
+#[test]
+fn test_merge_from() {
+    let mut map = important_cities()
+        .into_iter()
+        .map(|(k, v)| (k, vec![v]))
+        .collect::<DirectIdMap<_, _>>();
+    map.merge_from(
+        vec![
+            (California, vec!["San Diego"]),
+            (NorthDakota, vec!["Fargo"]),
+        ],
+        |key, existing, incoming| {
+            assert_eq!(key, California);
+            existing.extend(incoming);
+        },
+    );
+    assert_eq!(map.len(), 4);
+    assert_eq!(map[California], vec!["Los Angeles", "San Diego"]);
+    assert_eq!(map[NorthDakota], vec!["Fargo"]);
+    assert_eq!(map[Arizona], vec!["Phoenix"]);
+}
+
+#[test]
+fn test_from_sorted_iter() {
+    let sorted = important_cities().into_iter().collect_vec();
+    let map = DirectIdMap::from_sorted_iter(sorted.iter().copied()).unwrap();
+    assert_eq!(map, important_cities());
+    assert_eq!(map.len(), 3);
+    assert!(DirectIdMap::<u32, ()>::from_sorted_iter([])
+        .unwrap()
+        .is_empty());
+
+    let err = DirectIdMap::from_sorted_iter(sorted.iter().rev().copied()).unwrap_err();
+    assert_eq!(err.key, California);
+    assert_eq!(err.previous, NewYork);
+    assert_eq!(
+        err.to_string(),
+        "key California is not greater than the previous key NewYork"
+    );
+    let err = DirectIdMap::from_sorted_iter([(3u32, 'a'), (5, 'b'), (5, 'c')]).unwrap_err();
+    assert_eq!((err.key, err.previous), (5, 5));
+}
+
+#[test]
+fn test_double_ended() {
+    let mut map = important_cities();
+    map.insert(NorthDakota, "Fargo");
+    let mut iter = map.iter();
+    assert_eq!(iter.len(), 4);
+    assert_eq!(iter.next(), Some((Arizona, &"Phoenix")));
+    assert_eq!(iter.next_back(), Some((NorthDakota, &"Fargo")));
+    assert_eq!(iter.len(), 2);
+    assert_eq!(iter.next_back(), Some((NewYork, &"New York City")));
+    assert_eq!(iter.next(), Some((California, &"Los Angeles")));
+    assert_eq!(iter.len(), 0);
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+
+    assert_eq!(
+        map.keys().rev().take(2).collect_vec(),
+        vec![NorthDakota, NewYork]
+    );
+    assert_eq!(
+        map.values().rev().copied().collect_vec(),
+        vec!["Fargo", "New York City", "Los Angeles", "Phoenix"]
+    );
+    for value in map.values_mut().rev().take(1) {
+        *value = "Bismarck";
+    }
+    let mut iter = map.iter_mut();
+    assert_eq!(iter.next_back(), Some((NorthDakota, &mut "Bismarck")));
+    assert_eq!(iter.size_hint(), (3, Some(3)));
+    let mut into_iter = map.into_iter();
+    assert_eq!(into_iter.next_back(), Some((NorthDakota, "Bismarck")));
+    assert_eq!(into_iter.len(), 3);
+    assert_eq!(
+        into_iter.rev().map(|(k, _)| k).collect_vec(),
+        vec![NewYork, California, Arizona]
+    );
+}
+
+#[test]
+fn test_exact_size_with_holes() {
+    // every third id up to 300, leaving most slots empty
+    let mut map: DirectIdMap<u32, u32> = (0..100).map(|i| (i * 3, i)).collect();
+    map.remove(297);
+    assert_eq!(map.len(), 99);
+    assert_eq!(map.as_slots().len(), 298);
+    assert_eq!(map.iter().len(), map.len());
+    assert_eq!(map.iter_mut().len(), map.len());
+    assert_eq!(map.keys().len(), map.len());
+    assert_eq!(map.values().len(), map.len());
+    assert_eq!(map.values_mut().len(), map.len());
+    let collected = map.values().collect_vec();
+    assert_eq!(collected.capacity(), map.len());
+
+    let mut iter = map.iter();
+    for consumed in 1..=10 {
+        iter.next();
+        iter.next_back();
+        assert_eq!(iter.len(), map.len() - 2 * consumed);
+        assert_eq!(iter.size_hint(), (iter.len(), Some(iter.len())));
+    }
+    assert_eq!(iter.len(), iter.count());
+
+    let mut keys = map.keys().skip(40);
+    assert_eq!(keys.len(), 59);
+    assert_eq!(keys.nth(50), Some(270));
+    assert_eq!(keys.len(), 8);
+
+    let mut into_iter = map.clone().into_iter();
+    into_iter.by_ref().take(33).for_each(drop);
+    assert_eq!(into_iter.len(), 66);
+    assert_eq!(into_iter.count(), 66);
+    let mut drain = map.drain();
+    drain.next_back();
+    assert_eq!(drain.len(), 98);
+}
+
+#[test]
+fn test_into_iter() {
+    let mut iter = important_cities().into_iter();
+    assert_eq!(format!("{iter:?}"), "IntoIter { len: 3, .. }");
+    assert_eq!(iter.next_back(), Some((NewYork, "New York City")));
+    assert_eq!(iter.len(), 2);
+    assert_eq!(format!("{iter:?}"), "IntoIter { len: 2, .. }");
+    assert_eq!(iter.next(), Some((Arizona, "Phoenix")));
+    assert_eq!(iter.next(), Some((California, "Los Angeles")));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+}
+
+#[test]
+fn test_hash() {
+    fn hash(map: &DirectIdMap<KnownState, &'static str>) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        map.hash(&mut hasher);
+        hasher.finish()
+    }
+    let first = important_cities();
+    let second = important_cities()
+        .into_iter()
+        .rev()
+        .collect::<DirectIdMap<_, _>>();
+    assert_eq!(first, second);
+    assert_eq!(hash(&first), hash(&second));
+    let mut third = second.clone();
+    third.insert(Arizona, "Tucson");
+    assert_ne!(hash(&first), hash(&third));
+}
+
+#[test]
+fn test_map_values() {
+    let map = important_cities();
+    let lengths = map.map_values(|state, city| {
+        assert_eq!(city, state.city());
+        city.len()
+    });
+    assert_eq!(lengths.len(), 3);
+    assert_eq!(lengths.max_id(), Some(NewYork.to_int()));
+    assert_eq!(lengths[Arizona], "Phoenix".len());
+    assert_eq!(lengths[California], "Los Angeles".len());
+    assert_eq!(lengths[NewYork], "New York City".len());
+    assert_eq!(lengths.get(NewMexico), None);
+}
+
+#[test]
+fn test_get_or_insert() {
+    let mut map = important_cities();
+    assert_eq!(*map.get_or_insert(Arizona, "Tucson"), "Phoenix");
+    assert_eq!(*map.get_or_insert(NewMexico, "Albuquerque"), "Albuquerque");
+    assert_eq!(*map.get_or_insert_with(NorthDakota, || "Fargo"), "Fargo");
+    // already present, so the closure isn't called
+    *map.get_or_insert_with(NorthDakota, || unreachable!()) = "Bismarck";
+    assert_eq!(map.len(), 5);
+    assert_eq!(map[NorthDakota], "Bismarck");
+    check_cities(&[Arizona, California, NewMexico, NewYork], &map);
+}
+
+#[test]
+fn test_get_key_value() {
+    let mut map = important_cities();
+    assert_eq!(map.get_key_value(Arizona), Some((Arizona, &"Phoenix")));
+    assert_eq!(map.get_key_value(NewMexico), None);
+    let (key, city) = map.get_key_value_mut(California).unwrap();
+    assert_eq!(key, California);
+    *city = "San Diego";
+    assert_eq!(map[California], "San Diego");
+    assert_eq!(map.get_key_value_mut(NorthDakota), None);
+}
+
+#[test]
+fn test_get_by_id() {
+    let mut map = important_cities();
+    assert_eq!(map.get_by_id(Arizona.to_int()), Some(&"Phoenix"));
+    assert_eq!(map.get_by_id(NewMexico.to_int()), None);
+    // not a valid KnownState
+    assert_eq!(map.get_by_id(17), None);
+    assert_eq!(map.get_by_id(usize::MAX), None);
+    *map.get_by_id_mut(California.to_int()).unwrap() = "San Diego";
+    assert_eq!(map[California], "San Diego");
+    assert_eq!(map.get_by_id_mut(17), None);
+    assert!(map.contains_id(NewYork.to_int()));
+    assert!(!map.contains_id(NorthDakota.to_int()));
+    assert!(!map.contains_id(usize::MAX));
+    assert!(map.contains_key(NewYork));
+    assert!(!map.contains_key(NorthDakota));
+
+    let map: DirectIdMap<u64, ()> = direct_idmap! { 3 => () };
+    assert_eq!(map.get_by_id(u64::MAX), None);
+    assert!(map.contains_id(3));
+}
+
+#[test]
+fn test_keys_set() {
+    let cities = important_cities();
+    let keys = cities.keys_set();
+    assert_eq!(keys.len(), cities.len());
+    for state in cities.keys() {
+        assert!(keys.contains(state));
+    }
+    assert!(!keys.contains(NewMexico));
+    assert_eq!(
+        keys.iter().collect::<Vec<_>>(),
+        cities.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        DirectIdMap::<KnownState, ()>::new().keys_set(),
+        DirectIdSet::new()
+    );
+}
+
+#[test]
+fn test_iter_intersection() {
+    let mut cities = important_cities();
+    let populations: DirectIdMap<KnownState, u32> = direct_idmap! {
+        NewYork => 8_336_817,
+        NorthDakota => 125_990,
+        Arizona => 1_650_070,
+    };
+    assert_eq!(
+        cities.iter_intersection(&populations).collect::<Vec<_>>(),
+        [
+            (Arizona, &"Phoenix", &1_650_070),
+            (NewYork, &"New York City", &8_336_817),
+        ]
+    );
+    assert_eq!(
+        cities
+            .iter_intersection(&populations)
+            .rev()
+            .map(|(state, _, _)| state)
+            .collect::<Vec<_>>(),
+        [NewYork, Arizona]
+    );
+    for (_, city, &population) in cities.iter_intersection_mut(&populations) {
+        if population > 5_000_000 {
+            *city = "NYC";
+        }
+    }
+    assert_eq!(cities[NewYork], "NYC");
+    assert_eq!(cities[Arizona], "Phoenix");
+    assert_eq!(cities[California], "Los Angeles");
+    assert_eq!(
+        cities
+            .iter_intersection(&DirectIdMap::<KnownState, ()>::new())
+            .next(),
+        None
+    );
+}
+
+#[test]
+fn test_keys_difference() {
+    let cities = important_cities();
+    let populations: DirectIdMap<KnownState, u32> = direct_idmap! {
+        NewYork => 8_336_817,
+        NorthDakota => 125_990,
+    };
+    assert_eq!(
+        cities.keys_difference(&populations).collect::<Vec<_>>(),
+        [Arizona, California]
+    );
+    assert_eq!(
+        populations.keys_difference(&cities).collect::<Vec<_>>(),
+        [NorthDakota]
+    );
+    assert_eq!(
+        cities
+            .keys_symmetric_difference(&populations)
+            .collect::<Vec<_>>(),
+        [Arizona, California, NorthDakota]
+    );
+    assert_eq!(
+        populations
+            .keys_symmetric_difference(&cities)
+            .rev()
+            .collect::<Vec<_>>(),
+        [NorthDakota, California, Arizona]
+    );
+    assert!(!cities.is_disjoint(&populations));
+    assert!(!cities.keys_subset_of(&populations));
+    let small: DirectIdMap<KnownState, ()> = direct_idmap! { NewYork => () };
+    assert!(small.keys_subset_of(&cities));
+    assert!(small.keys_subset_of(&populations));
+    assert!(!cities.keys_subset_of(&small));
+    let dakota: DirectIdMap<KnownState, ()> = direct_idmap! { NorthDakota => () };
+    assert!(cities.is_disjoint(&dakota));
+    assert!(DirectIdMap::<KnownState, ()>::new().keys_subset_of(&dakota));
+}
+
+#[test]
+fn test_from_dense_vec() {
+    let map =
+        DirectIdMap::<KnownState, _>::from_dense_vec(vec!["Phoenix", "Los Angeles", "Albuquerque"]);
+    assert_eq!(map.len(), 3);
+    assert_eq!(
+        map.iter().collect::<Vec<_>>(),
+        [
+            (Arizona, &"Phoenix"),
+            (California, &"Los Angeles"),
+            (NewMexico, &"Albuquerque"),
+        ]
+    );
+    let empty = DirectIdMap::<u8, ()>::from_dense_vec(Vec::new());
+    assert!(empty.is_empty());
+    assert_eq!(empty.capacity(), 0);
+    let bytes = DirectIdMap::<u8, usize>::from_dense_vec((0..256).collect());
+    assert_eq!(bytes.len(), 256);
+    assert_eq!(bytes[255], 255);
+}
+
+#[test]
+#[should_panic(expected = "index 256 is not a valid id")]
+fn test_from_dense_vec_invalid() {
+    DirectIdMap::<u8, usize>::from_dense_vec((0..257).collect());
+}
+
+#[test]
+fn test_from_entries() {
+    let cities = DirectIdMap::from([
+        (Arizona, "Phoenix"),
+        (NewYork, "New York City"),
+        (California, "Los Angeles"),
+    ]);
+    assert_eq!(cities, important_cities());
+    let cities = DirectIdMap::from(vec![
+        (California, "San Diego"),
+        (NewYork, "New York City"),
+        (Arizona, "Phoenix"),
+        (California, "Los Angeles"),
+    ]);
+    assert_eq!(cities, important_cities());
+    assert!(DirectIdMap::<KnownState, ()>::from([]).is_empty());
+}
+
+#[test]
+fn test_eq_std_maps() {
+    let cities = important_cities();
+    let mut expected = HashMap::from([
+        (California, "Los Angeles"),
+        (NewYork, "New York City"),
+        (Arizona, "Phoenix"),
+    ]);
+    assert_eq!(cities, expected);
+    assert_eq!(expected, cities);
+    let sorted = expected
+        .iter()
+        .map(|(&k, &v)| (k, v))
+        .collect::<BTreeMap<_, _>>();
+    assert_eq!(cities, sorted);
+    assert_eq!(sorted, cities);
+
+    expected.insert(California, "San Diego");
+    assert_ne!(cities, expected);
+    expected.remove(&California);
+    assert_ne!(cities, expected);
+    assert_ne!(expected, cities);
+    let mut sorted = sorted;
+    sorted.insert(NorthDakota, "Fargo");
+    assert_ne!(cities, sorted);
+    assert_ne!(sorted, cities);
+}
+
+#[test]
+fn test_std_map_conversions() {
+    let cities = important_cities();
+    let hashed = HashMap::<_, _>::from(cities.clone());
+    assert_eq!(hashed.len(), 3);
+    assert_eq!(hashed[&NewYork], "New York City");
+    assert_eq!(DirectIdMap::from(hashed), cities);
+    let sorted = BTreeMap::from(cities.clone());
+    assert_eq!(
+        sorted.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>(),
+        cities.iter().map(|(k, &v)| (k, v)).collect::<Vec<_>>()
+    );
+    let roundtrip = DirectIdMap::from(sorted);
+    assert_eq!(roundtrip, cities);
+    assert!(roundtrip.capacity() > NewYork.to_int());
+    assert_eq!(roundtrip.max_id(), Some(NewYork.to_int()));
+    assert!(DirectIdMap::<KnownState, ()>::from(BTreeMap::new()).is_empty());
+}
+
+#[test]
+fn test_truncate() {
+    struct DropCounter<'a>(&'a Cell<usize>);
+    impl Drop for DropCounter<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+    let drops = Cell::new(0);
+    let mut map: DirectIdMap<u32, DropCounter<'_>> = [1, 4, 5, 9, 20]
+        .into_iter()
+        .map(|id| (id, DropCounter(&drops)))
+        .collect();
+    map.truncate(5);
+    map.truncate(7);
+    assert_eq!(map.len(), 5);
+    assert_eq!(drops.get(), 0);
+    map.truncate(2);
+    assert_eq!(map.keys().collect::<Vec<_>>(), [1, 4]);
+    assert_eq!(map.max_id(), Some(4));
+    assert_eq!(drops.get(), 3);
+    map.truncate(0);
+    assert!(map.is_empty());
+    assert_eq!(map.max_id(), None);
+    assert_eq!(drops.get(), 5);
+    drop(map);
+    assert_eq!(drops.get(), 5);
+}
+
+#[test]
+fn test_as_slots() {
+    let cities = important_cities();
+    assert_eq!(
+        cities.as_slots(),
+        [
+            Some("Phoenix"),
+            Some("Los Angeles"),
+            None,
+            Some("New York City")
+        ]
+    );
+    let weights: DirectIdMap<u32, f64> = (0..1000).map(|id| (id, f64::from(id))).collect();
+    let total: f64 = weights.as_slots().iter().flatten().sum();
+    assert_eq!(total, weights.values().sum());
+    assert!(DirectIdMap::<u32, ()>::new().as_slots().is_empty());
+}
+
+#[test]
+fn test_iter_with_holes() {
+    let mut cities = important_cities();
+    cities.insert(NorthDakota, "Fargo");
+    cities.remove(NorthDakota);
+    let slots = cities.iter_with_holes();
+    assert_eq!(slots.len(), NewYork.to_int() + 1);
+    assert_eq!(
+        slots.collect::<Vec<_>>(),
+        [
+            (0, Some(&"Phoenix")),
+            (1, Some(&"Los Angeles")),
+            (2, None),
+            (3, Some(&"New York City")),
+        ]
+    );
+    for (id, value) in cities.iter_with_holes_mut() {
+        match value {
+            Some(city) if id == California.to_int() => *city = "San Diego",
+            _ => {}
+        }
+    }
+    assert_eq!(cities[California], "San Diego");
+    let mut slots = cities.iter_with_holes_mut();
+    assert_eq!(slots.next_back().map(|(id, _)| id), Some(3));
+    assert_eq!(slots.len(), 3);
+    assert_eq!(DirectIdMap::<u32, ()>::new().iter_with_holes().len(), 0);
+}
+
+#[test]
+fn test_missing_ids() {
+    let mut cities = important_cities();
+    assert_eq!(
+        cities.missing_ids().collect::<Vec<_>>(),
+        [NewMexico.to_int()]
+    );
+    assert_eq!(cities.hole_count(), 1);
+    cities.remove(Arizona);
+    cities.insert(NorthDakota, "Fargo");
+    cities.remove(NorthDakota);
+    assert_eq!(
+        cities.missing_ids().rev().collect::<Vec<_>>(),
+        [NewMexico.to_int(), Arizona.to_int()]
+    );
+    assert_eq!(cities.hole_count(), 2);
+    let dense = DirectIdMap::<u32, _>::from_dense_vec(vec![1, 2, 3]);
+    assert_eq!(dense.missing_ids().next(), None);
+    assert_eq!(dense.hole_count(), 0);
+    assert_eq!(DirectIdMap::<u32, ()>::new().missing_ids().next(), None);
+    assert_eq!(DirectIdMap::<u32, ()>::new().hole_count(), 0);
+}
+
+#[test]
+fn test_retain_in_set() {
+    let mut cities = important_cities();
+    let survivors: DirectIdSet<KnownState> = [NewYork, Arizona, NorthDakota].into_iter().collect();
+    cities.retain_in_set(&survivors);
+    assert_eq!(
+        cities,
+        direct_idmap! {
+            Arizona => "Phoenix",
+            NewYork => "New York City",
+        }
+    );
+    cities.retain_in_set(&DirectIdSet::new());
+    assert!(cities.is_empty());
+
+    let mut ids: DirectIdMap<u32, u32> = (0..10).map(|id| (id, id * 2)).collect();
+    let evens: DirectIdSet<u32> = (0..1000).step_by(2).collect();
+    ids.retain_in_set(&evens);
+    assert_eq!(ids.keys().collect::<Vec<_>>(), [0, 2, 4, 6, 8]);
+    assert_eq!(ids.len(), 5);
+
+    // spans several words, with the set shorter than the map
+    let mut ids: DirectIdMap<u32, u32> = (0..300).map(|id| (id, id)).collect();
+    let kept: DirectIdSet<u32> = (0..64).chain([65, 127, 128, 150]).collect();
+    ids.retain_in_set(&kept);
+    assert_eq!(ids.len(), 68);
+    assert_eq!(ids.keys().collect::<DirectIdSet<_>>(), kept);
+}
+
+#[test]
+fn test_remove_in_set() {
+    let mut cities = important_cities();
+    let removed: DirectIdSet<KnownState> = [California, NorthDakota].into_iter().collect();
+    cities.remove_in_set(&removed);
+    assert_eq!(
+        cities,
+        direct_idmap! {
+            Arizona => "Phoenix",
+            NewYork => "New York City",
+        }
+    );
+    let mut ids: DirectIdMap<u32, u32> = (0..10).map(|id| (id, id * 2)).collect();
+    let evens: DirectIdSet<u32> = (0..1000).step_by(2).collect();
+    ids.remove_in_set(&evens);
+    assert_eq!(ids.keys().collect::<Vec<_>>(), [1, 3, 5, 7, 9]);
+    assert_eq!(ids.len(), 5);
+}
+
+#[test]
+fn test_remove_all() {
+    let mut cities = important_cities();
+    let removed = cities.remove_all([NewYork, NorthDakota, Arizona, NewYork]);
+    assert_eq!(removed, [(NewYork, "New York City"), (Arizona, "Phoenix")]);
+    assert_eq!(cities.len(), 1);
+    assert_eq!(cities[California], "Los Angeles");
+    assert_eq!(cities.remove_all(Vec::<KnownState>::new()), []);
+}
+
+/// Count the number of times the table is reallocated while extending the map.
+fn count_extend_reallocations(ids: impl IntoIterator<Item = u32>) -> usize {
+    let mut map = DirectIdMap::<u32, u32>::new();
+    let mut reallocations = 0;
+    for id in ids {
+        let capacity = map.capacity();
+        map.extend([(id, id)]);
+        if map.capacity() != capacity {
+            reallocations += 1;
+        }
+    }
+    reallocations
+}
+
+#[test]
+fn test_extend_growth() {
+    // sparse ids grow geometrically by id, rather than by the number of entries
+    assert!(count_extend_reallocations((1..=100).map(|i| i * 1000)) <= 8);
+    // dense ids amortize like a `Vec`
+    assert!(count_extend_reallocations(0..100_000) <= 20);
+    // a single large id allocates exactly once
+    assert_eq!(count_extend_reallocations([1_000_000]), 1);
+}
+
+#[test]
+fn test_debug_ids() {
+    let cities = important_cities();
+    assert_eq!(
+        format!("{:?}", cities.debug_ids()),
+        r#"{0: "Phoenix", 1: "Los Angeles", 3: "New York City"}"#
+    );
+    assert_eq!(format!("{:?}", cities.keys_set().debug_ids()), "{0..=1, 3}");
+    assert_eq!(
+        format!("{:?}", DirectIdMap::<u32, ()>::new().debug_ids()),
+        "{}"
+    );
+}
+
+#[test]
+fn test_with_max_key() {
+    assert_eq!(DirectIdMap::<u8, ()>::with_max_key(0).capacity(), 1);
+    assert_eq!(DirectIdMap::<u8, ()>::with_max_key(1).capacity(), 2);
+    let mut bytes = DirectIdMap::<u8, u8>::with_max_key(u8::MAX);
+    assert_eq!(bytes.capacity(), 256);
+    assert!(bytes.is_empty());
+    bytes.extend((0..=u8::MAX).map(|id| (id, id)));
+    assert_eq!(bytes.capacity(), 256);
+    let mut cities = DirectIdMap::with_max_key(NewYork);
+    let capacity = cities.capacity();
+    cities.extend(important_cities());
+    assert_eq!(cities.capacity(), capacity);
+}
+
+#[test]
+#[should_panic(expected = "Storing id would exhaust memory: u64::MAX")]
+fn test_with_max_key_overflow() {
+    DirectIdMap::<u64, ()>::with_max_key(u64::MAX);
+}
+
+#[test]
+fn test_insert_and_get() {
+    let mut cities = important_cities();
+    let city = cities.insert_and_get(California, "San Francisco");
+    assert_eq!(*city, "San Francisco");
+    *city = "San Diego";
+    assert_eq!(cities[California], "San Diego");
+    assert_eq!(cities.len(), 3);
+    *cities.insert_and_get(NorthDakota, "Bismarck") = "Fargo";
+    assert_eq!(cities[NorthDakota], "Fargo");
+    assert_eq!(cities.len(), 4);
+}
+
+#[test]
+fn test_cursor_mut() {
+    let mut map: DirectIdMap<u32, u32> = (0..20)
+        .filter(|id| id % 3 != 0)
+        .map(|id| (id, id))
+        .collect();
+    let mut cursor = map.cursor_mut();
+    let mut removed = Vec::new();
+    let mut keep = true;
+    while let Some((key, value)) = cursor.current() {
+        assert_eq!(key, *value);
+        if keep {
+            *value *= 10;
+            cursor.advance();
+        } else {
+            removed.push(cursor.remove_current().unwrap().0);
+        }
+        keep = !keep;
+    }
+    assert_eq!(cursor.key(), None);
+    assert_eq!(cursor.remove_current(), None);
+    cursor.advance();
+    assert_eq!(cursor.current(), None);
+    assert_eq!(removed, [2, 5, 8, 11, 14, 17]);
+    assert_eq!(
+        map.iter().map(|(k, &v)| (k, v)).collect::<Vec<_>>(),
+        [
+            (1, 10),
+            (4, 40),
+            (7, 70),
+            (10, 100),
+            (13, 130),
+            (16, 160),
+            (19, 190)
+        ]
+    );
+    assert_eq!(map.len(), 7);
+
+    let mut cities = important_cities();
+    let mut cursor = cities.cursor_mut();
+    assert_eq!(cursor.key(), Some(Arizona));
+    while cursor.remove_current().is_some() {}
+    assert!(cities.is_empty());
+    assert_eq!(DirectIdMap::<u32, ()>::new().cursor_mut().key(), None);
+}
+
+#[test]
+fn test_iter_from() {
+    let cities = important_cities();
+    assert_eq!(
+        cities
+            .iter_from(California)
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>(),
+        [California, NewYork]
+    );
+    assert_eq!(
+        cities.iter_from(NewMexico).collect::<Vec<_>>(),
+        [(NewYork, &"New York City")]
+    );
+    assert_eq!(cities.iter_from(NorthDakota).next(), None);
+    assert_eq!(
+        cities.iter_from_id(0).map(|(k, _)| k).collect::<Vec<_>>(),
+        [Arizona, California, NewYork]
+    );
+    assert_eq!(cities.iter_from_id(NewYork.to_int()).count(), 1);
+    assert_eq!(cities.iter_from_id(17).next(), None);
+    assert_eq!(cities.iter_from_id(usize::MAX).next(), None);
+
+    // paginate through the map, resuming after the last id seen
+    let map: DirectIdMap<u32, u32> = (0..100).step_by(7).map(|id| (id, id)).collect();
+    let mut pages = Vec::new();
+    let mut next = 0;
+    loop {
+        let page = map
+            .iter_from_id(next)
+            .take(5)
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>();
+        let Some(&last) = page.last() else { break };
+        next = last + 1;
+        pages.push(page);
+    }
+    assert_eq!(pages.len(), 3);
+    assert_eq!(pages.concat(), map.keys().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_get_or_insert_default() {
+    let mut populations = DirectIdMap::<KnownState, Vec<u32>>::new();
+    populations.get_or_insert_default(NewYork).push(8_336_817);
+    populations.get_or_insert_default(NewYork).push(8_804_190);
+    assert!(populations.get_or_insert_default(Arizona).is_empty());
+    assert_eq!(populations.len(), 2);
+    assert_eq!(populations[NewYork], [8_336_817, 8_804_190]);
+
+    // a panicking function must not corrupt the length
+    let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+        populations.get_or_insert_with(California, || panic!("failed to count"));
+    }));
+    assert!(result.is_err());
+    assert_eq!(populations.len(), 2);
+    assert_eq!(populations.get(California), None);
+}
+
+#[test]
+fn test_iter_masked() {
+    let mut cities = important_cities();
+    let dirty: DirectIdSet<KnownState> = [NorthDakota, NewYork, Arizona, NewMexico]
+        .into_iter()
+        .collect();
+    assert_eq!(
+        cities.iter_masked(&dirty).collect::<Vec<_>>(),
+        [(Arizona, &"Phoenix"), (NewYork, &"New York City")]
+    );
+    for (_, city) in cities.iter_masked_mut(&dirty) {
+        *city = "dirty";
+    }
+    assert_eq!(cities[Arizona], "dirty");
+    assert_eq!(cities[California], "Los Angeles");
+    assert_eq!(cities[NewYork], "dirty");
+    assert_eq!(cities.iter_masked(&DirectIdSet::new()).next(), None);
+
+    let map: DirectIdMap<u32, u32> = (0..100).map(|id| (id, id)).collect();
+    let large: DirectIdSet<u32> = [3, 50, 99, 100, 5000].into_iter().collect();
+    assert_eq!(
+        map.iter_masked(&large).map(|(k, _)| k).collect::<Vec<_>>(),
+        [3, 50, 99]
+    );
+    let small: DirectIdSet<u32> = [0, 1, 7].into_iter().collect();
+    assert_eq!(
+        map.iter_masked(&small).map(|(k, _)| k).collect::<Vec<_>>(),
+        [0, 1, 7]
+    );
+    // fused once the table runs out, even though the mask continues
+    let mut masked = map.iter_masked(&large);
+    assert_eq!(masked.by_ref().count(), 3);
+    assert_eq!(masked.next(), None);
+    assert_eq!(masked.next(), None);
+}
+
+#[test]
+fn test_swap() {
+    let mut cities = important_cities();
+    assert!(cities.swap(Arizona, NewYork));
+    assert_eq!(cities[Arizona], "New York City");
+    assert_eq!(cities[NewYork], "Phoenix");
+    assert!(cities.swap(California, California));
+    assert_eq!(cities[California], "Los Angeles");
+    assert!(!cities.swap(California, NewMexico));
+    assert!(!cities.swap(NorthDakota, California));
+    assert!(!cities.swap(NorthDakota, NorthDakota));
+    assert_eq!(cities[California], "Los Angeles");
+    assert_eq!(cities.len(), 3);
+    assert_eq!(cities.get(NewMexico), None);
+}
+
+#[test]
+fn test_rename_key() {
+    let mut cities = important_cities();
+    assert_eq!(cities.rename_key(California, NorthDakota), Ok(()));
+    assert_eq!(cities.get(California), None);
+    assert_eq!(cities[NorthDakota], "Los Angeles");
+    assert_eq!(cities.len(), 3);
+    assert_eq!(
+        cities.rename_key(California, NewMexico),
+        Err(RenameError::MissingKey(California))
+    );
+    assert_eq!(
+        cities.rename_key(Arizona, NewYork),
+        Err(RenameError::OccupiedKey(NewYork))
+    );
+    assert_eq!(cities[Arizona], "Phoenix");
+    assert_eq!(cities.rename_key(Arizona, Arizona), Ok(()));
+    assert_eq!(
+        cities.rename_key(NewMexico, NewMexico),
+        Err(RenameError::MissingKey(NewMexico))
+    );
+    assert_eq!(
+        cities.keys().collect::<Vec<_>>(),
+        [Arizona, NewYork, NorthDakota]
+    );
+    assert_eq!(
+        RenameError::OccupiedKey(NewYork).to_string(),
+        "key NewYork is already in the map"
+    );
+
+    let mut ids: DirectIdMap<u32, &str> = direct_idmap! { 2 => "foo" };
+    ids.rename_key(2, 1000).unwrap();
+    assert_eq!(ids[1000], "foo");
+    assert_eq!(ids.min_id(), Some(1000));
+}
+
+#[test]
+fn test_extract() {
+    let mut cities = important_cities();
+    let extracted = cities.extract([NewYork, NorthDakota, Arizona, NewYork]);
+    assert_eq!(
+        extracted,
+        direct_idmap! {
+            Arizona => "Phoenix",
+            NewYork => "New York City",
+        }
+    );
+    assert_eq!(cities, direct_idmap! { California => "Los Angeles" });
+    let set: DirectIdSet<KnownState> = [California, NewMexico].into_iter().collect();
+    let extracted = cities.extract(&set);
+    assert_eq!(extracted.len(), 1);
+    assert!(cities.is_empty());
+    assert!(cities.extract([Arizona]).is_empty());
+}
+
+#[test]
+fn test_try_reserve() {
+    let mut map = DirectIdMap::<u64, u64>::new();
+    map.try_reserve(10).unwrap();
+    assert!(map.capacity() >= 10);
+    assert!(map.try_reserve(usize::MAX).is_err());
+    map.try_reserve_for_key(100).unwrap();
+    let capacity = map.capacity();
+    assert!(capacity > 100);
+    map.insert(100, 1);
+    assert_eq!(map.capacity(), capacity);
+    // already reserved, so nothing changes
+    map.try_reserve_for_key(3).unwrap();
+    assert_eq!(map.capacity(), capacity);
+    // absurd ids fail gracefully instead of aborting
+    assert!(map.try_reserve_for_key(u64::MAX).is_err());
+    assert!(map.try_reserve_for_key(1 << 62).is_err());
+    assert_eq!(map.capacity(), capacity);
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn test_partition() {
+    let (short, long) = important_cities().partition(|_, city| city.len() < 10);
+    assert_eq!(short, direct_idmap! { Arizona => "Phoenix" });
+    assert_eq!(
+        long,
+        direct_idmap! {
+            California => "Los Angeles",
+            NewYork => "New York City",
+        }
+    );
+    assert_eq!(short.max_id(), Some(Arizona.to_int()));
+    let (all, none) = important_cities().partition(|_, _| true);
+    assert_eq!(all, important_cities());
+    assert!(none.is_empty());
+    let (none, all) = important_cities().partition(|_, _| false);
+    assert!(none.is_empty());
+    assert_eq!(all, important_cities());
+    let (left, right) = DirectIdMap::<KnownState, ()>::new().partition(|_, _| unreachable!());
+    assert!(left.is_empty() && right.is_empty());
+}
+
+#[test]
+fn test_map_keys() {
+    let ranked = important_cities()
+        .map_keys(|state, _| match state {
+            NewYork => 0u8,
+            California => 1,
+            _ => 7,
+        })
+        .unwrap();
+    assert_eq!(
+        ranked,
+        direct_idmap! {
+            0 => "New York City",
+            1 => "Los Angeles",
+            7 => "Phoenix",
+        }
+    );
+    // every city has an odd number of characters
+    let collision = important_cities().map_keys(|_, city| city.len() as u32 % 2);
+    let error = collision.unwrap_err();
+    assert_eq!(
+        error,
+        DuplicateKeyError {
+            key: 1,
+            source_key: California,
+            value: "Los Angeles",
+            mapped: direct_idmap! { 1 => "Phoenix" },
+            remaining: direct_idmap! { NewYork => "New York City" },
+        }
+    );
+    assert_eq!(error.to_string(), "multiple keys were mapped to 1");
+    assert_eq!(error.remaining.len(), 1);
+    // every value can be recovered
+    let mut recovered = error.remaining;
+    recovered.insert(error.source_key, error.value);
+    recovered.insert(Arizona, error.mapped[1]);
+    assert_eq!(recovered, important_cities());
+    assert!(DirectIdMap::<KnownState, ()>::new()
+        .map_keys(|_, _| -> u32 { unreachable!() })
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_zip_mut_with() {
+    let mut populations: DirectIdMap<KnownState, u32> = direct_idmap! {
+        Arizona => 1_600_000,
+        NewYork => 8_300_000,
+        NorthDakota => 125_000,
+    };
+    let growth: DirectIdMap<KnownState, u32> = direct_idmap! {
+        California => 1_000,
+        NewYork => 2_000,
+        NorthDakota => 3_000,
+    };
+    let mut visited = Vec::new();
+    populations.zip_mut_with(&growth, |state, population, growth| {
+        visited.push(state);
+        *population += growth;
+    });
+    assert_eq!(visited, [NewYork, NorthDakota]);
+    assert_eq!(
+        populations,
+        direct_idmap! {
+            Arizona => 1_600_000,
+            NewYork => 8_302_000,
+            NorthDakota => 128_000,
+        }
+    );
+}
+
+#[test]
+fn test_default_map() {
+    let mut counts = DefaultDirectIdMap::<KnownState, u32>::new();
+    assert_eq!(counts[NewYork], 0);
+    assert_eq!(*counts.get_or_default(NewYork), 0);
+    assert_eq!(counts.get(NewYork), None);
+    assert!(counts.is_empty());
+    counts[NewYork] += 2;
+    assert_eq!(counts.get_mut(Arizona), None);
+    *counts.get_mut_or_insert_default(Arizona) += 1;
+    assert_eq!(counts.get(Arizona), Some(&1));
+    counts[NewYork] += 1;
+    assert_eq!(counts[NewYork], 3);
+    assert_eq!(counts[California], 0);
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts.remove(Arizona), Some(1));
+    assert_eq!(counts[Arizona], 0);
+    assert_eq!(counts.len(), 1);
+
+    let mut cities = DefaultDirectIdMap::from_map(important_cities(), "unknown");
+    assert_eq!(cities[NorthDakota], "unknown");
+    assert_eq!(cities[Arizona], "Phoenix");
+    cities[NorthDakota] = "Fargo";
+    assert_eq!(cities.len(), 4);
+    assert_eq!(*cities.default_value(), "unknown");
+    let inner = cities.into_inner();
+    assert_eq!(inner[NorthDakota], "Fargo");
+}
+
+#[test]
+fn test_iter_ids() {
+    let cities = important_cities();
+    let mut ids = cities.iter_ids();
+    assert_eq!(ids.len(), 3);
+    assert_eq!(ids.next(), Some((0, &"Phoenix")));
+    assert_eq!(ids.next_back(), Some((3, &"New York City")));
+    assert_eq!(ids.len(), 1);
+    assert_eq!(ids.next(), Some((1, &"Los Angeles")));
+    assert_eq!(ids.next(), None);
+    assert_eq!(ids.next(), None);
+    let mut ids = cities.ids();
+    assert_eq!(ids.len(), 3);
+    assert_eq!(ids.next_back(), Some(3));
+    assert_eq!(ids.collect::<Vec<_>>(), [0, 1]);
+    assert_eq!(DirectIdMap::<u32, ()>::new().ids().len(), 0);
+}
+
+#[test]
+fn test_retain_filter_map() {
+    struct DropCounter<'a>(&'a Cell<usize>, u32);
+    impl Drop for DropCounter<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+    let drops = Cell::new(0);
+    let mut map: DirectIdMap<u32, DropCounter<'_>> =
+        (0..10).map(|id| (id, DropCounter(&drops, id))).collect();
+    map.retain_filter_map(|id, value| {
+        assert_eq!(id, value.1);
+        match id % 3 {
+            0 => None,
+            1 => Some(value),
+            _ => Some(DropCounter(value.0, value.1 * 10)),
+        }
+    });
+    // removed values and replaced values are each dropped exactly once
+    assert_eq!(drops.get(), 4 + 3);
+    assert_eq!(
+        map.iter().map(|(k, v)| (k, v.1)).collect::<Vec<_>>(),
+        [(1, 1), (2, 20), (4, 4), (5, 50), (7, 7), (8, 80)]
+    );
+    assert_eq!(map.len(), 6);
+
+    let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+        map.retain_filter_map(|id, value| {
+            assert!(id < 5, "failed to filter");
+            Some(value)
+        });
+    }));
+    assert!(result.is_err());
+    // the panicking entry is removed, and later entries are untouched
+    assert_eq!(map.keys().collect::<Vec<_>>(), [1, 2, 4, 7, 8]);
+    assert_eq!(map.len(), 5);
+    drop(map);
+    assert_eq!(drops.get(), 4 + 3 + 6);
+}
+
+#[test]
+fn test_vacant_key() {
+    let mut cities = important_cities();
+    assert_eq!(cities.vacant_key(), Some(NewMexico));
+    assert_eq!(cities.insert_vacant_value("Albuquerque"), NewMexico);
+    assert_eq!(cities.vacant_key(), Some(NorthDakota));
+    assert_eq!(cities.insert_vacant_value("Fargo"), NorthDakota);
+    // past the last valid state
+    assert_eq!(cities.vacant_key(), None);
+    cities.remove(California);
+    assert_eq!(cities.vacant_key(), Some(California));
+    cities.clear();
+    assert_eq!(cities.vacant_key(), Some(Arizona));
+
+    let mut slab = DirectIdMap::<u32, u32>::new();
+    for expected in 0..10 {
+        assert_eq!(slab.insert_vacant_value(expected * 10), expected);
+    }
+    slab.remove(7);
+    slab.remove(3);
+    assert_eq!(slab.insert_vacant_value(30), 3);
+    assert_eq!(slab.insert_vacant_value(70), 7);
+    assert_eq!(slab.vacant_key(), Some(10));
+    assert_eq!(slab.len(), 10);
+}
+
+#[test]
+#[should_panic(expected = "no vacant key")]
+fn test_insert_vacant_value_full() {
+    let mut bytes = DirectIdMap::<u8, ()>::from_dense_vec(vec![(); 256]);
+    bytes.insert_vacant_value(());
+}
+
+#[test]
+fn test_contains_all_any() {
+    let cities = important_cities();
+    let required: DirectIdSet<KnownState> = [Arizona, NewYork].into_iter().collect();
+    assert!(cities.contains_all(&required));
+    assert!(cities.contains_any(&required));
+    let beyond: DirectIdSet<KnownState> = [Arizona, NorthDakota].into_iter().collect();
+    assert!(!cities.contains_all(&beyond));
+    assert!(cities.contains_any(&beyond));
+    let missing: DirectIdSet<KnownState> = [NewMexico, NorthDakota].into_iter().collect();
+    assert!(!cities.contains_all(&missing));
+    assert!(!cities.contains_any(&missing));
+    assert!(cities.contains_all(&DirectIdSet::new()));
+    assert!(!cities.contains_any(&DirectIdSet::new()));
+    let empty = DirectIdMap::<KnownState, ()>::new();
+    assert!(empty.contains_all(&DirectIdSet::new()));
+    assert!(!empty.contains_all(&required));
+    assert!(!empty.contains_any(&required));
+}
+
+#[test]
+fn test_drain_range() {
+    let mut map: DirectIdMap<u32, u32> = (0..20).step_by(2).map(|id| (id, id)).collect();
+    assert_eq!(
+        map.drain_range(4..10).collect::<Vec<_>>(),
+        [(4, 4), (6, 6), (8, 8)]
+    );
+    assert_eq!(map.len(), 7);
+    assert_eq!(
+        map.drain_range(10..=12).rev().collect::<Vec<_>>(),
+        [(12, 12), (10, 10)]
+    );
+    assert_eq!(map.len(), 5);
+    // no entries in range
+    assert_eq!(map.drain_range(3..4).next(), None);
+    assert_eq!(map.drain_range(100..).next(), None);
+    assert_eq!(map.len(), 5);
+    // dropping early still removes the rest of the range
+    let mut drain = map.drain_range(..17);
+    assert_eq!(drain.next(), Some((0, 0)));
+    drop(drain);
+    assert_eq!(map.keys().collect::<Vec<_>>(), [18]);
+    assert_eq!(map.len(), 1);
+
+    let mut cities = important_cities();
+    cities.drain_range((Bound::Excluded(Arizona), Bound::Unbounded));
+    assert_eq!(cities, direct_idmap! { Arizona => "Phoenix" });
+}
+
+#[test]
+fn test_from_keys() {
+    let counts = DirectIdMap::<KnownState, u32>::from_keys([NewYork, Arizona, NewYork]);
+    assert_eq!(counts, direct_idmap! { Arizona => 0, NewYork => 0 });
+    let keys = DirectIdMap::<KnownState, Vec<u32>>::from_keys(important_cities().keys());
+    assert_eq!(keys.keys_set(), important_cities().keys_set());
+    assert!(DirectIdMap::<u32, ()>::from_keys([]).is_empty());
+}
+
+#[test]
+fn test_is_dense() {
+    let mut cities = important_cities();
+    assert!(!cities.is_dense());
+    assert_eq!(cities.density(), 0.75);
+    cities.remove(NewYork);
+    // dense again after removing the tail, even though the slot remains
+    assert!(cities.is_dense());
+    assert_eq!(cities.density(), 1.0);
+    cities.insert(NewMexico, "Albuquerque");
+    assert!(cities.is_dense());
+    cities.remove(Arizona);
+    assert!(!cities.is_dense());
+    assert!(DirectIdMap::<u32, ()>::new().is_dense());
+    assert_eq!(DirectIdMap::<u32, ()>::new().density(), 1.0);
+    let sparse: DirectIdMap<u32, ()> = direct_idmap! { 99 => () };
+    assert_eq!(sparse.density(), 0.01);
+}
+
+#[test]
+fn test_into_dense_vec() {
+    let mut cities = important_cities();
+    cities = cities.into_dense_vec().unwrap_err();
+    assert_eq!(cities, important_cities());
+    cities.remove(NewYork);
+    assert_eq!(cities.into_dense_vec(), Ok(vec!["Phoenix", "Los Angeles"]));
+    assert_eq!(DirectIdMap::<u32, ()>::new().into_dense_vec(), Ok(vec![]));
+    let values: Vec<u64> = (0..100).collect();
+    let map = DirectIdMap::<u32, u64>::from_dense_vec(values.clone());
+    assert_eq!(map.into_dense_vec(), Ok(values));
+}
+
+#[test]
+fn test_raw_slots() {
+    let slots = important_cities().into_raw_slots();
+    assert_eq!(
+        slots,
+        [
+            Some("Phoenix"),
+            Some("Los Angeles"),
+            None,
+            Some("New York City")
+        ]
+    );
+    let cities = DirectIdMap::<KnownState, _>::from_raw_slots(slots).unwrap();
+    assert_eq!(cities, important_cities());
+    assert_eq!(cities.len(), 3);
+
+    let mut slots = vec![None; 8];
+    slots[6] = Some("Nowhere");
+    slots[3] = Some("New York City");
+    let error = DirectIdMap::<KnownState, _>::from_raw_slots(slots).unwrap_err();
+    assert_eq!(error.id, 6);
+    assert_eq!(error.to_string(), "slot 6 is not a valid id");
+    // trailing empty slots past the valid ids are allowed
+    let cities = DirectIdMap::<KnownState, &str>::from_raw_slots(vec![None; 8]).unwrap();
+    assert!(cities.is_empty());
+    let bytes = DirectIdMap::<u8, ()>::from_raw_slots(vec![Some(()); 257]);
+    assert_eq!(bytes.unwrap_err().id, 256);
+}
+
+#[test]
+fn test_apply_patch() {
+    let mut cities = important_cities();
+    let summary = cities.apply_patch([
+        (NorthDakota, None),
+        (Arizona, None),
+        (NewMexico, Some("Albuquerque")),
+        (California, Some("San Francisco")),
+        (NorthDakota, Some("Fargo")),
+        (NorthDakota, None),
+        (California, Some("Los Angeles")),
+    ]);
+    assert_eq!(
+        cities,
+        direct_idmap! {
+            California => "Los Angeles",
+            NewMexico => "Albuquerque",
+            NewYork => "New York City",
+        }
+    );
+    assert_eq!(
+        summary,
+        PatchSummary {
+            inserted: 2,
+            updated: 2,
+            removed: 2,
+        }
+    );
+    assert_eq!(cities.apply_patch([]), PatchSummary::default());
+}
+
+#[test]
+fn test_remove_keys_of() {
+    // other is smaller
+    let mut cities = important_cities();
+    let other: DirectIdMap<KnownState, ()> = direct_idmap! { Arizona => () };
+    assert_eq!(cities.remove_keys_of(&other), 1);
+    assert_eq!(cities.len(), 2);
+    assert!(!cities.contains_key(Arizona));
+    // self is smaller
+    let mut cities = direct_idmap! { Arizona => "Phoenix" };
+    let other: DirectIdMap<KnownState, u32> = direct_idmap! {
+        Arizona => 1,
+        NewMexico => 2,
+        NorthDakota => 3,
+    };
+    assert_eq!(cities.remove_keys_of(&other), 1);
+    assert!(cities.is_empty());
+    // equal sizes
+    let mut cities = important_cities();
+    let mut other = important_cities();
+    other.remove(California);
+    assert_eq!(cities.remove_keys_of(&other), 2);
+    assert_eq!(cities, direct_idmap! { California => "Los Angeles" });
+    assert_eq!(cities.remove_keys_of(&other), 0);
+    assert_eq!(
+        cities.remove_keys_of(&DirectIdMap::<KnownState, ()>::new()),
+        0
+    );
+    assert_eq!(cities.len(), 1);
+}
+
+#[test]
+fn test_iter_fold() {
+    let cities = important_cities();
+    let mut iter = cities.iter();
+    assert_eq!(iter.next(), Some((Arizona, &"Phoenix")));
+    let rest = iter.fold(Vec::new(), |mut acc, entry| {
+        acc.push(entry);
+        acc
+    });
+    assert_eq!(
+        rest,
+        [(California, &"Los Angeles"), (NewYork, &"New York City")]
+    );
+    let ids = cities.keys().fold(0, |acc, key| acc * 10 + key.to_int());
+    assert_eq!(ids, 13);
+    let mut lengths = Vec::new();
+    cities
+        .into_values()
+        .for_each(|city| lengths.push(city.len()));
+    assert_eq!(lengths, [7, 11, 13]);
+}
+
+#[test]
+fn test_empty_does_not_allocate() {
+    fn assert_unallocated(map: &DirectIdMap<KnownState, String>) {
+        assert_eq!(map.capacity(), 0, "{map:?}");
+        assert_eq!(map.allocated_bytes(), 0, "{map:?}");
+    }
+    assert_unallocated(&DirectIdMap::new());
+    assert_unallocated(&DirectIdMap::default());
+    assert_unallocated(&DirectIdMap::with_capacity(0));
+    assert_unallocated(&DirectIdMap::from_iter(core::iter::empty::<(
+        KnownState,
+        String,
+    )>()));
+    assert_unallocated(&DirectIdMap::from_keys(core::iter::empty()));
+    assert_unallocated(&DirectIdMap::from(Vec::new()));
+    assert_unallocated(&DirectIdMap::from(BTreeMap::new()));
+    let mut map = DirectIdMap::new();
+    map.clear();
+    map.extend(core::iter::empty::<(KnownState, String)>());
+    map.retain(|_, _| false);
+    assert_unallocated(&map);
+    assert_unallocated(&map.clone());
+    let mut map = important_cities()
+        .into_iter()
+        .map(|(state, city)| (state, city.to_owned()))
+        .collect::<DirectIdMap<_, _>>();
+    map.clear();
+    map.shrink_to_fit();
+    assert_unallocated(&map);
+}
+
 /// List the biggest cities in each state except for `NewMexico` and `NorthDakota`,
 /// intentionally excluding them to provide a better test case.
 fn important_cities() -> DirectIdMap<KnownState, &'static str> {
@@ -146,7 +2038,9 @@ fn important_cities() -> DirectIdMap<KnownState, &'static str> {
         California => "Los Angeles"
     }
 }
-#[derive(IntegerId, Debug, Copy, Clone, PartialEq, Serialize, Deserialize, Ord, PartialOrd, Eq)]
+#[derive(
+    IntegerId, Debug, Copy, Clone, PartialEq, Serialize, Deserialize, Ord, PartialOrd, Eq, Hash,
+)]
 enum KnownState {
     Arizona,
     California,
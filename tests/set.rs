@@ -1,4 +1,4 @@
-#![allow(missing_docs)]
+#![allow(missing_docs, clippy::bool_assert_comparison)]
 use intid::IntegerId;
 use itertools::Itertools;
 use serde_derive::{Deserialize, Serialize};
@@ -21,6 +21,100 @@ fn test_remove() {
     assert_eq!(m.remove(NorthDakota), false);
 }
 
+#[test]
+fn test_debug_ids() {
+    let set: IdSet<u32> = idset![12, 0, 1, 2, 5, 7, 8];
+    assert_eq!(format!("{:?}", set.debug_ids()), "{0..=2, 5, 7..=8, 12}");
+    assert_eq!(format!("{:?}", IdSet::<u32>::new().debug_ids()), "{}");
+    assert_eq!(
+        format!("{:#?}", idset![3u32, 4].debug_ids()),
+        "{\n    3..=4,\n}"
+    );
+}
+
+#[test]
+fn test_with_max_key() {
+    assert!(IdSet::<u8>::with_max_key(0).capacity() >= 1);
+    assert!(IdSet::<u8>::with_max_key(1).capacity() >= 2);
+    let mut bytes = IdSet::<u8>::with_max_key(u8::MAX);
+    assert_eq!(bytes.capacity(), 256);
+    assert!(bytes.is_empty());
+    bytes.extend(0..=u8::MAX);
+    assert_eq!(bytes.capacity(), 256);
+    assert_eq!(bytes.len(), 256);
+}
+
+#[test]
+#[should_panic(expected = "Storing id would exhaust memory: u64::MAX")]
+fn test_with_max_key_overflow() {
+    IdSet::<u64>::with_max_key(u64::MAX);
+}
+
+#[test]
+fn test_empty_does_not_allocate() {
+    fn assert_unallocated(set: &IdSet<u32>) {
+        assert_eq!(set.capacity(), 0, "{set:?}");
+        assert_eq!(set.allocated_bytes(), 0, "{set:?}");
+    }
+    assert_unallocated(&IdSet::new());
+    assert_unallocated(&IdSet::default());
+    assert_unallocated(&IdSet::with_capacity(0));
+    assert_unallocated(&IdSet::from_iter(core::iter::empty::<u32>()));
+    let mut set = IdSet::new();
+    set.clear();
+    set.extend(core::iter::empty::<u32>());
+    set.retain(|_| false);
+    assert_unallocated(&set);
+    assert_unallocated(&set.clone());
+}
+
+#[test]
+fn test_union() {
+    // disjoint, with differing capacities
+    let small: IdSet<u32> = idset![1, 5];
+    let large: IdSet<u32> = idset![64, 130, 0];
+    assert_eq!(small.union(&large).collect_vec(), [0, 1, 5, 64, 130]);
+    assert_eq!(large.union(&small).collect_vec(), [0, 1, 5, 64, 130]);
+    assert_eq!(small.union_set(&large), idset![0, 1, 5, 64, 130]);
+    assert_eq!(small.union_set(&large).len(), 5);
+    // overlapping
+    let left: IdSet<u32> = idset![2, 63, 64, 100];
+    let right: IdSet<u32> = idset![63, 100, 127, 128];
+    assert_eq!(left.union(&right).collect_vec(), [2, 63, 64, 100, 127, 128]);
+    assert_eq!(left.union_set(&right).len(), 6);
+    // nested
+    let inner: IdSet<KnownState> = idset![Arizona, NewYork];
+    let outer = important_states();
+    assert_eq!(
+        inner.union(&outer).collect_vec(),
+        outer.iter().collect_vec()
+    );
+    assert_eq!(outer.union_set(&inner), outer);
+    assert_eq!(inner.union_set(&outer).len(), outer.len());
+    // empty
+    let empty = IdSet::new();
+    assert_eq!(empty.union(&empty).next(), None);
+    assert_eq!(
+        empty.union(&large).collect_vec(),
+        large.iter().collect_vec()
+    );
+    assert_eq!(large.union_set(&empty), large);
+    assert_eq!(format!("{:?}", small.union(&empty)), "[1, 5]");
+}
+
+#[test]
+fn test_retain_large_ids() {
+    let mut set: IdSet<u32> = idset![3, 40, 64, 100, 200];
+    let mut seen = Vec::new();
+    set.retain(|id| {
+        seen.push(id);
+        id % 2 == 0
+    });
+    assert_eq!(seen, [3, 40, 64, 100, 200]);
+    assert_eq!(set, idset![40, 64, 100, 200]);
+    assert_eq!(set.len(), 4);
+}
+
 #[test]
 fn test_eq() {
     let first = important_states();
@@ -29,6 +123,22 @@ fn test_eq() {
     let second = second.iter().collect::<IdSet<_>>();
 
     assert_eq!(first, second);
+
+    // unused capacity doesn't affect equality
+    let mut third = important_states();
+    third.insert(NorthDakota);
+    third.remove(NorthDakota);
+    assert_eq!(first, third);
+    assert_eq!(third, first);
+    let mut large = IdSet::<u32>::new();
+    large.insert(1000);
+    large.remove(1000);
+    assert_eq!(large, IdSet::new());
+    large.insert(3);
+    assert_eq!(large, idset!(3));
+    assert_ne!(large, idset!(3, 900));
+    third.remove(Arizona);
+    assert_ne!(first, third);
 }
 
 #[test]
@@ -50,6 +160,18 @@ fn test_clone() {
     assert_eq!(original, cloned);
 }
 
+#[test]
+fn test_clone_from() {
+    let original = important_states();
+    let mut scratch = IdSet::with_capacity(100);
+    scratch.insert(NorthDakota);
+    for _ in 0..3 {
+        scratch.clone_from(&original);
+        assert_eq!(scratch, original);
+        assert_eq!(scratch.len(), 3);
+    }
+}
+
 #[test]
 fn test_index() {
     let set = important_states();
@@ -155,6 +277,60 @@ fn test_insert_expand() {
     assert_eq!(data.insert(512), true);
 }
 
+#[test]
+fn test_capacity() {
+    let mut data = IdSet::<u32>::new();
+    assert_eq!(data.capacity(), 0);
+    data.insert(99);
+    assert!(data.capacity() >= 100);
+    let capacity = data.capacity();
+    data.clear();
+    assert_eq!(data.capacity(), capacity);
+    assert!(data.is_empty());
+    // growth is amortized
+    data.insert(capacity as u32);
+    assert!(data.capacity() >= 2 * capacity);
+    let capacity = data.capacity();
+    data.extend(0..capacity as u32);
+    assert_eq!(data.capacity(), capacity);
+    // cloning into a larger set keeps its capacity
+    let mut large = IdSet::with_capacity(4 * capacity);
+    let large_capacity = large.capacity();
+    large.clone_from(&idset![5]);
+    assert_eq!(large.capacity(), large_capacity);
+    assert_eq!(large, idset![5]);
+    large.clone_from(&data);
+    assert_eq!(large, data);
+}
+
+#[test]
+fn test_allocated_bytes() {
+    let mut data = IdSet::<u32>::new();
+    assert_eq!(data.allocated_bytes(), 0);
+    data.insert(3);
+    let small = data.allocated_bytes();
+    assert!(small > 0);
+    data.insert(10_000);
+    assert!(data.allocated_bytes() >= 10_000 / 8);
+    let mut last_size = data.allocated_bytes();
+    for id in [50_000, 200_000] {
+        data.insert(id);
+        assert!(data.allocated_bytes() > last_size);
+        last_size = data.allocated_bytes();
+    }
+    assert_eq!(data.allocated_bytes(), data.capacity() / 8);
+
+    data.remove(200_000);
+    data.shrink_to_fit();
+    assert!(data.allocated_bytes() < last_size);
+    assert!(data.capacity() > 50_000);
+    assert_eq!(data.allocated_bytes(), data.capacity() / 8);
+    assert_eq!(data, idset![3, 10_000, 50_000]);
+    data.clear();
+    data.shrink_to_fit();
+    assert_eq!(data.allocated_bytes(), 0);
+}
+
 #[derive(IntegerId, Copy, Clone, Eq, Debug, PartialEq)]
 struct ExampleWrapper(u16);
 #[derive(IntegerId, Copy, Clone, Eq, Debug, PartialEq)]